@@ -1,16 +1,24 @@
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use std::str::FromStr;
-use syn::{parenthesized, DeriveInput, Lit, LitStr, Result, Type};
+use syn::{parenthesized, DeriveInput, LitStr, Result, Type};
 
 #[cfg(feature = "op")]
 use crate::Addable;
 
 const FILLER: &str = "filler";
+const NAME: &str = "name";
 const ATTRIBUTE: &str = "attribute";
+const SKIP: &str = "skip";
 const EXTENDABLE: &str = "extendable";
 const EMPTY_VALUE: &str = "empty_value";
+const IS_EMPTY: &str = "is_empty";
 const ADDABLE: &str = "addable";
+const NESTING: &str = "nesting";
+const FILLABLE: &str = "fillable";
+const MERGE_KEYS: &str = "merge_keys";
+const MERGE_MEMBERS: &str = "merge_members";
+const PATCH: &str = "patch";
 
 pub(crate) struct Filler {
     visibility: syn::Visibility,
@@ -19,40 +27,61 @@ pub(crate) struct Filler {
     generics: syn::Generics,
     attributes: Vec<TokenStream>,
     fields: Vec<Field>,
+    /// The patch of the same struct, converted from and into the filler
+    patch: Option<crate::patch::Patch>,
 }
 
 enum FillerType {
     Option,
     /// The type with `Default`, `Extend`, `IntoIterator` and `is_empty` implementations
-    Extendable(Ident),
+    Extendable,
+    /// The type implementing `struct_patch::traits::Fillable`
+    Fillable,
+    /// The map with `Default`, `IntoIterator`, `is_empty` and `entry` implementations, filled
+    /// with the missing keys
+    MergeKeys,
+    /// The set with `Default`, `Extend`, `IntoIterator` and `is_empty` implementations, filled
+    /// with the missing members
+    MergeMembers,
     /// The type with a value defined for empty
-    NativeValue(Lit),
+    NativeValue(crate::EmptyValue),
+    /// The struct deriving `Filler`, filled by its own filler
+    #[cfg(feature = "nesting")]
+    Nesting(Type),
+    /// The `Option` of the struct deriving `Filler`, `None` is filled with the default value
+    /// filled by the filler, and `Some` is filled by the filler
+    #[cfg(feature = "nesting")]
+    OptionNesting(Type),
 }
 
 impl FillerType {
-    fn inner(&self) -> &Ident {
-        if let FillerType::Extendable(ident) = self {
-            ident
+    fn value(&self) -> &crate::EmptyValue {
+        if let FillerType::NativeValue(empty) = self {
+            empty
         } else {
-            panic!("Only FillerType::Extendable has inner indent")
+            panic!("Only FillerType::NativeValue has value")
         }
     }
-    fn value(&self) -> &Lit {
-        if let FillerType::NativeValue(lit) = self {
-            lit
-        } else {
-            panic!("Only FillerType::NativeValue has value")
+    #[cfg(feature = "nesting")]
+    fn nested(&self) -> &Type {
+        match self {
+            FillerType::Nesting(ty) | FillerType::OptionNesting(ty) => ty,
+            _ => panic!("Only FillerType::Nesting and FillerType::OptionNesting have nested type"),
         }
     }
 }
 
 struct Field {
     ident: Option<Ident>,
+    /// The type of the field in the filler struct
     ty: Type,
     attributes: Vec<TokenStream>,
     fty: FillerType,
     #[cfg(feature = "op")]
     addable: Addable,
+    /// The first paragraph of the doc comment, used as the description in the JSON schema
+    #[cfg(feature = "schemars")]
+    doc: Option<String>,
 }
 
 impl Filler {
@@ -65,6 +94,7 @@ impl Filler {
             generics,
             attributes,
             fields,
+            ..
         } = self;
 
         let filler_struct_fields = fields
@@ -87,23 +117,73 @@ impl Filler {
 
         let extendable_field_names = fields
             .iter()
-            .filter(|f| matches!(f.fty, FillerType::Extendable(_)))
+            .filter(|f| matches!(f.fty, FillerType::Extendable))
             .map(|f| f.ident.as_ref())
             .collect::<Vec<_>>();
 
         let extendable_field_types = fields
             .iter()
-            .filter(|f| matches!(f.fty, FillerType::Extendable(_)))
-            .map(|f| f.fty.inner())
+            .filter(|f| matches!(f.fty, FillerType::Extendable))
+            .map(|f| &f.ty)
             .collect::<Vec<_>>();
 
         #[cfg(feature = "op")]
         let extendable_field_addable = fields
             .iter()
-            .filter(|f| matches!(f.fty, FillerType::Extendable(_)))
+            .filter(|f| matches!(f.fty, FillerType::Extendable))
             .map(|f| !matches!(f.addable, Addable::Disable))
             .collect::<Vec<_>>();
 
+        let fillable_field_names = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::Fillable))
+            .map(|f| f.ident.as_ref())
+            .collect::<Vec<_>>();
+
+        // The conflicting values of the fields which are not addable panic when the fillers are
+        // added
+        #[cfg(feature = "op")]
+        let fillable_field_appends = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::Fillable))
+            .map(|f| {
+                let ident = &f.ident;
+                if matches!(f.addable, Addable::Disable) {
+                    quote! {
+                        panic!("`{}` conflict in fillers, please use `#[filler(addable)]`", stringify!(#ident))
+                    }
+                } else {
+                    quote! {
+                        struct_patch::traits::Fillable::append(&mut self.#ident, rhs.#ident);
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let merge_keys_field_names = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::MergeKeys))
+            .map(|f| f.ident.as_ref())
+            .collect::<Vec<_>>();
+
+        let merge_members_field_names = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::MergeMembers))
+            .map(|f| f.ident.as_ref())
+            .collect::<Vec<_>>();
+
+        let merge_field_names = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::MergeKeys | FillerType::MergeMembers))
+            .map(|f| f.ident.as_ref())
+            .collect::<Vec<_>>();
+
+        let merge_field_types = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::MergeKeys | FillerType::MergeMembers))
+            .map(|f| &f.ty)
+            .collect::<Vec<_>>();
+
         let native_value_field_names = fields
             .iter()
             .filter(|f| matches!(f.fty, FillerType::NativeValue(_)))
@@ -115,6 +195,14 @@ impl Filler {
             .filter(|f| matches!(f.fty, FillerType::NativeValue(_)))
             .map(|f| f.fty.value())
             .collect::<Vec<_>>();
+        let native_value_field_empty_checks = |receiver| {
+            crate::empty_checks(
+                receiver,
+                &native_value_field_names,
+                &native_value_field_empty_values,
+            )
+        };
+        let native_value_field_self_empty_checks = native_value_field_empty_checks(quote!(self));
 
         #[cfg(feature = "op")]
         let native_value_field_addable = fields
@@ -123,9 +211,62 @@ impl Filler {
             .map(|f| !matches!(f.addable, Addable::Disable))
             .collect::<Vec<_>>();
 
+        #[cfg(feature = "nesting")]
+        let nesting_field_names = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::Nesting(_)))
+            .map(|f| f.ident.as_ref())
+            .collect::<Vec<_>>();
+        #[cfg(not(feature = "nesting"))]
+        let nesting_field_names: Vec<Option<&Ident>> = Vec::new();
+
+        #[cfg(feature = "nesting")]
+        let nesting_field_types = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::Nesting(_)))
+            .map(|f| f.fty.nested())
+            .collect::<Vec<_>>();
+        #[cfg(not(feature = "nesting"))]
+        let nesting_field_types: Vec<&Type> = Vec::new();
+
+        #[cfg(feature = "nesting")]
+        let nesting_filler_types = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::Nesting(_)))
+            .map(|f| &f.ty)
+            .collect::<Vec<_>>();
+        #[cfg(not(feature = "nesting"))]
+        let nesting_filler_types: Vec<&Type> = Vec::new();
+
+        #[cfg(feature = "nesting")]
+        let option_nesting_field_names = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::OptionNesting(_)))
+            .map(|f| f.ident.as_ref())
+            .collect::<Vec<_>>();
+        #[cfg(not(feature = "nesting"))]
+        let option_nesting_field_names: Vec<Option<&Ident>> = Vec::new();
+
+        #[cfg(feature = "nesting")]
+        let option_nesting_field_types = fields
+            .iter()
+            .filter(|f| matches!(f.fty, FillerType::OptionNesting(_)))
+            .map(|f| f.fty.nested())
+            .collect::<Vec<_>>();
+        #[cfg(not(feature = "nesting"))]
+        let option_nesting_field_types: Vec<&Type> = Vec::new();
+
+        // The requested `JsonSchema` is generated to make all the properties optional, so it is
+        // not derived
+        #[cfg(feature = "schemars")]
+        let schema = attributes
+            .iter()
+            .any(|a| crate::has_derive(a, "JsonSchema"));
         let mapped_attributes = attributes
             .iter()
             .map(|a| {
+                #[cfg(feature = "schemars")]
+                let a = &crate::remove_derive(a, "JsonSchema");
                 quote! {
                     #[#a]
                 }
@@ -156,7 +297,28 @@ impl Filler {
                         }
                     )*
                     #(
-                        if self.#native_value_field_names != #native_value_field_empty_values {
+                        if !struct_patch::traits::Fillable::is_empty(&self.#fillable_field_names) {
+                            return false
+                        }
+                    )*
+                    #(
+                        if !(#native_value_field_self_empty_checks) {
+                            return false
+                        }
+                    )*
+                    #(
+                        if !self.#merge_field_names.is_empty() {
+                            return false
+                        }
+                    )*
+                    #(
+                        if !struct_patch::traits::Status::is_empty(&self.#nesting_field_names) {
+                            return false
+                        }
+                    )*
+                    // Even an empty nested filler fills `None` with the default value
+                    #(
+                        if self.#option_nesting_field_names.is_some() {
                             return false
                         }
                     )*
@@ -167,6 +329,8 @@ impl Filler {
         #[cfg(not(feature = "status"))]
         let status_impl = quote!();
 
+        #[cfg(feature = "op")]
+        let native_value_field_rhs_empty_checks = native_value_field_empty_checks(quote!(rhs));
         #[cfg(feature = "op")]
         let op_impl = quote! {
             #[automatically_derived]
@@ -185,11 +349,11 @@ impl Filler {
 
                 fn add(mut self, rhs: Self) -> Self {
                     #(
-                        if self.#native_value_field_names == #native_value_field_empty_values {
+                        if #native_value_field_self_empty_checks {
                             self.#native_value_field_names = rhs.#native_value_field_names;
                         } else if #native_value_field_addable {
-                            self.#native_value_field_names = self.#native_value_field_names + rhs.#native_value_field_names;
-                        } else if rhs.#native_value_field_names != #native_value_field_empty_values {
+                            self.#native_value_field_names = core::ops::Add::add(self.#native_value_field_names, rhs.#native_value_field_names);
+                        } else if !(#native_value_field_rhs_empty_checks) {
                             panic!("`{}` conflict in fillers, please use `#[filler(addable)]`", stringify!(#native_value_field_names))
                         }
                     )*
@@ -202,6 +366,22 @@ impl Filler {
                             panic!("`{}` conflict in fillers, please use `#[filler(addable)]`", stringify!(#extendable_field_names))
                         }
                     )*
+                    #(
+                        if struct_patch::traits::Fillable::is_empty(&self.#fillable_field_names) {
+                            self.#fillable_field_names = rhs.#fillable_field_names;
+                        } else if !struct_patch::traits::Fillable::is_empty(&rhs.#fillable_field_names) {
+                            #fillable_field_appends
+                        }
+                    )*
+                    // The entries of the left filler are kept, as the first applied filler wins
+                    #(
+                        for (k, v) in rhs.#merge_keys_field_names {
+                            self.#merge_keys_field_names.entry(k).or_insert(v);
+                        }
+                    )*
+                    #(
+                        self.#merge_members_field_names.extend(rhs.#merge_members_field_names);
+                    )*
                     #(
                         if let Some(b) = self.#option_field_names {
                             if let Some(a) = rhs.#option_field_names {
@@ -217,6 +397,15 @@ impl Filler {
                             self.#option_field_names = rhs.#option_field_names;
                         }
                     )*
+                    #(
+                        self.#nesting_field_names = self.#nesting_field_names + rhs.#nesting_field_names;
+                    )*
+                    #(
+                        self.#option_nesting_field_names = match (self.#option_nesting_field_names, rhs.#option_nesting_field_names) {
+                            (Some(a), Some(b)) => Some(a + b),
+                            (a, b) => a.or(b),
+                        };
+                    )*
                     self
                 }
             }
@@ -225,12 +414,58 @@ impl Filler {
         #[cfg(not(feature = "op"))]
         let op_impl = quote!();
 
+        // The values of the right filler win, the left values are only kept where the right
+        // field is empty, and the keys of the maps and sets are merged with the right entries
+        // winning, as a later layer of configuration overrides the earlier ones
+        #[cfg(feature = "merge")]
+        let merge_impl = {
+            let merged_fields = fields
+                .iter()
+                .filter_map(|f| {
+                    let ident = f.ident.as_ref()?;
+                    let merged = f.merge_token_stream();
+                    Some(quote!(#ident: #merged,))
+                })
+                .collect::<Vec<_>>();
+            #[cfg(feature = "op")]
+            let shl_impl = quote! {
+                #[automatically_derived]
+                impl #generics core::ops::Shl<Self> for #name #generics #where_clause {
+                    type Output = Self;
+
+                    fn shl(self, rhs: Self) -> Self {
+                        struct_patch::traits::Merge::merge(self, rhs)
+                    }
+                }
+            };
+            #[cfg(not(feature = "op"))]
+            let shl_impl = quote!();
+            quote! {
+                #[automatically_derived]
+                impl #generics struct_patch::traits::Merge for #name #generics #where_clause {
+                    fn merge(self, other: Self) -> Self {
+                        #name {
+                            #(#merged_fields)*
+                        }
+                    }
+                }
+
+                #shl_impl
+            }
+        };
+        #[cfg(not(feature = "merge"))]
+        let merge_impl = quote!();
+
+        let unfilled_impl = self.unfilled_token_stream();
+        let defaults_impl = self.defaults_token_stream();
+        let patch_impl = self.patch_token_stream();
+
         let filler_impl = quote! {
             #[automatically_derived]
             impl #generics struct_patch::traits::Filler< #name #generics > for #struct_name #generics #where_clause  {
                 fn apply(&mut self, filler: #name #generics) {
                     #(
-                        if self.#native_value_field_names == #native_value_field_empty_values {
+                        if #native_value_field_self_empty_checks {
                             self.#native_value_field_names = filler.#native_value_field_names;
                         }
                     )*
@@ -239,6 +474,20 @@ impl Filler {
                             self.#extendable_field_names.extend(filler.#extendable_field_names.into_iter());
                         }
                     )*
+                    #(
+                        if struct_patch::traits::Fillable::is_empty(&self.#fillable_field_names) {
+                            self.#fillable_field_names = filler.#fillable_field_names;
+                        }
+                    )*
+                    #(
+                        for (k, v) in filler.#merge_keys_field_names {
+                            self.#merge_keys_field_names.entry(k).or_insert(v);
+                        }
+                    )*
+                    // The members already in the set are not replaced
+                    #(
+                        self.#merge_members_field_names.extend(filler.#merge_members_field_names);
+                    )*
                     #(
                         if let Some(v) = filler.#option_field_names {
                             if self.#option_field_names.is_none() {
@@ -246,36 +495,387 @@ impl Filler {
                             }
                         }
                     )*
+                    #(
+                        struct_patch::traits::Filler::apply(&mut self.#nesting_field_names, filler.#nesting_field_names);
+                    )*
+                    #(
+                        if let Some(filler) = filler.#option_nesting_field_names {
+                            match &mut self.#option_nesting_field_names {
+                                Some(v) => struct_patch::traits::Filler::apply(v, filler),
+                                // The nested struct needs `Default`
+                                None => {
+                                    let mut v: #option_nesting_field_types = Default::default();
+                                    struct_patch::traits::Filler::apply(&mut v, filler);
+                                    self.#option_nesting_field_names = Some(v);
+                                }
+                            }
+                        }
+                    )*
                 }
 
                 fn new_empty_filler() -> #name #generics {
                     #name {
                         #(#option_field_names: None,)*
-                        #(#extendable_field_names: #extendable_field_types::default(),)*
+                        #(#extendable_field_names: <#extendable_field_types as Default>::default(),)*
+                        #(#fillable_field_names: struct_patch::traits::Fillable::empty(),)*
+                        #(#merge_field_names: <#merge_field_types as Default>::default(),)*
                         #(#native_value_field_names: #native_value_field_empty_values,)*
+                        #(#nesting_field_names: <#nesting_field_types as struct_patch::traits::Filler<#nesting_filler_types>>::new_empty_filler(),)*
+                        #(#option_nesting_field_names: None,)*
                     }
                 }
             }
         };
 
+        #[cfg(feature = "schemars")]
+        let schema_impl = if schema {
+            let rename_all = crate::schema_rename_all(attributes)?;
+            let mut properties = vec![];
+            for f in fields.iter() {
+                let ident = match f.ident.as_ref() {
+                    Some(ident) => ident,
+                    None => continue,
+                };
+                let name = crate::schema_property_name(ident, &f.attributes, rename_all.as_deref());
+                let name = match name? {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let empty_value = match &f.fty {
+                    FillerType::NativeValue(empty) => Some(empty),
+                    _ => None,
+                };
+                properties.push(crate::schema_property(
+                    name,
+                    f.ty.to_token_stream(),
+                    f.doc.as_ref(),
+                    empty_value,
+                ));
+            }
+            crate::schema_impl(name, generics, properties)
+        } else {
+            quote!()
+        };
+        #[cfg(not(feature = "schemars"))]
+        let schema_impl = quote!();
+
         Ok(quote! {
             #filler_struct
             #status_impl
             #filler_impl
+            #unfilled_impl
+            #defaults_impl
+            #patch_impl
             #op_impl
+            #merge_impl
+            #schema_impl
         })
     }
 
+    /// Generate the `HasFiller` impl, whose `into_filler` converts a defaults instance of the same
+    /// type into a filler
+    fn defaults_token_stream(&self) -> TokenStream {
+        let Filler {
+            struct_name,
+            filler_struct_name: name,
+            generics,
+            fields,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+
+        let filler_fields = fields
+            .iter()
+            .filter_map(|f| {
+                let ident = f.ident.as_ref()?;
+                Some(match &f.fty {
+                    #[cfg(feature = "nesting")]
+                    FillerType::Nesting(_) => {
+                        quote!(#ident: struct_patch::traits::HasFiller::into_filler(self.#ident),)
+                    }
+                    #[cfg(feature = "nesting")]
+                    FillerType::OptionNesting(_) => quote! {
+                        #ident: self.#ident.map(struct_patch::traits::HasFiller::into_filler),
+                    },
+                    _ => quote!(#ident: self.#ident,),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        quote! {
+            #[automatically_derived]
+            impl #generics struct_patch::traits::HasFiller for #struct_name #generics #where_clause {
+                type Filler = #name #generics;
+
+                fn into_filler(self) -> #name #generics {
+                    #name {
+                        #(#filler_fields)*
+                    }
+                }
+            }
+        }
+    }
+
+    /// Generate `From` the patch of the same struct for the filler, and `into_patch` of the filler,
+    /// which convert the fields both of them have
+    fn patch_token_stream(&self) -> TokenStream {
+        let Filler {
+            struct_name,
+            filler_struct_name: name,
+            generics,
+            fields,
+            patch,
+            ..
+        } = self;
+        let patch = if let Some(patch) = patch {
+            patch
+        } else {
+            return quote!();
+        };
+        let patch_name = patch.patch_struct_name();
+        let where_clause = &generics.where_clause;
+
+        let mut from_patch = vec![];
+        let mut into_patch = vec![];
+        for (ident, shape) in patch.shaped_fields() {
+            let field = if let Some(f) = fields.iter().find(|f| f.ident.as_ref() == Some(ident)) {
+                f
+            } else {
+                continue;
+            };
+            // The value of the struct field is converted into the value of the filler field
+            let into_filler = match &field.fty {
+                #[cfg(feature = "nesting")]
+                FillerType::Nesting(_) => quote!(struct_patch::traits::HasFiller::into_filler(v)),
+                #[cfg(feature = "nesting")]
+                FillerType::OptionNesting(_) => {
+                    quote!(v.map(struct_patch::traits::HasFiller::into_filler))
+                }
+                _ => quote!(v),
+            };
+            #[cfg(feature = "nesting")]
+            let nested_filler = matches!(
+                field.fty,
+                FillerType::Nesting(_) | FillerType::OptionNesting(_)
+            );
+            #[cfg(not(feature = "nesting"))]
+            let nested_filler = false;
+
+            match shape {
+                crate::patch::PatchShape::Wrapped => {
+                    from_patch.push(quote! {
+                        if let Some(v) = patch.#ident {
+                            filler.#ident = #into_filler;
+                        }
+                    });
+                    if !nested_filler {
+                        let unfilled = field.unfilled_token_stream();
+                        into_patch.push(quote! {
+                            if !(#unfilled) {
+                                patch.#ident = Some(self.#ident);
+                            }
+                        });
+                    }
+                }
+                crate::patch::PatchShape::Unwrapped(is_set) => {
+                    from_patch.push(quote! {
+                        if #is_set {
+                            let v = patch.#ident;
+                            filler.#ident = #into_filler;
+                        }
+                    });
+                    if !nested_filler {
+                        let unfilled = field.unfilled_token_stream();
+                        into_patch.push(quote! {
+                            if !(#unfilled) {
+                                patch.#ident = self.#ident;
+                            }
+                        });
+                    }
+                }
+                #[cfg(feature = "nesting")]
+                crate::patch::PatchShape::Nesting => {
+                    if let FillerType::Nesting(_) = field.fty {
+                        from_patch.push(quote! {
+                            filler.#ident = From::from(patch.#ident);
+                        });
+                        into_patch.push(quote! {
+                            patch.#ident = self.#ident.into_patch();
+                        });
+                    }
+                }
+                #[cfg(not(feature = "nesting"))]
+                crate::patch::PatchShape::Nesting => {
+                    unreachable!("the nesting fields need `nesting` feature")
+                }
+            }
+        }
+
+        quote! {
+            #[automatically_derived]
+            impl #generics From<#patch_name #generics> for #name #generics #where_clause {
+                #[allow(unused_mut, unused_variables)]
+                fn from(patch: #patch_name #generics) -> Self {
+                    let mut filler = <#struct_name #generics as struct_patch::traits::Filler<#name #generics>>::new_empty_filler();
+                    #(#from_patch)*
+                    filler
+                }
+            }
+
+            #[automatically_derived]
+            impl #generics #name #generics #where_clause {
+                /// Convert the filler into the patch of the same struct, the empty fields of the
+                /// filler are left unset in the patch
+                #[allow(unused_mut)]
+                pub fn into_patch(self) -> #patch_name #generics {
+                    let mut patch = <#struct_name #generics as struct_patch::traits::Patch<#patch_name #generics>>::new_empty_patch();
+                    #(#into_patch)*
+                    patch
+                }
+            }
+        }
+    }
+
+    /// Generate `unfilled_fields` listing the fields of the struct which are still empty, and
+    /// `ensure_filled` and `apply_report` with the `alloc` feature, which list the fields of the
+    /// nested structs
+    fn unfilled_token_stream(&self) -> TokenStream {
+        let Filler {
+            struct_name,
+            generics,
+            fields,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+
+        let unfilled_checks = fields
+            .iter()
+            .filter_map(|f| {
+                let ident = f.ident.as_ref()?;
+                let ident_str = ident.to_string();
+                let unfilled = match &f.fty {
+                    #[cfg(feature = "nesting")]
+                    FillerType::Nesting(_) => {
+                        quote!(self.#ident.unfilled_fields().next().is_some())
+                    }
+                    #[cfg(feature = "nesting")]
+                    FillerType::OptionNesting(_) => quote! {
+                        self.#ident.as_ref().map_or(true, |v| v.unfilled_fields().next().is_some())
+                    },
+                    _ => f.unfilled_token_stream(),
+                };
+                Some(quote!((#ident_str, #unfilled)))
+            })
+            .collect::<Vec<_>>();
+        let field_count = unfilled_checks.len();
+
+        #[cfg(feature = "alloc")]
+        let ensure_filled = {
+            let name = &self.filler_struct_name;
+            let unfilled_paths = fields
+                .iter()
+                .filter_map(|f| {
+                    let ident = f.ident.as_ref()?;
+                    let ident_str = ident.to_string();
+                    Some(match &f.fty {
+                        #[cfg(feature = "nesting")]
+                        FillerType::Nesting(_) => {
+                            let prefix = format!("{}.", ident_str);
+                            quote! {
+                                self.#ident.__struct_patch_unfilled(&struct_patch::__private::prefixed(prefix, #prefix), unfilled);
+                            }
+                        }
+                        #[cfg(feature = "nesting")]
+                        FillerType::OptionNesting(_) => {
+                            let prefix = format!("{}.", ident_str);
+                            quote! {
+                                match &self.#ident {
+                                    Some(v) => v.__struct_patch_unfilled(&struct_patch::__private::prefixed(prefix, #prefix), unfilled),
+                                    None => unfilled.push(struct_patch::__private::prefixed(prefix, #ident_str)),
+                                }
+                            }
+                        }
+                        _ => {
+                            let is_unfilled = f.unfilled_token_stream();
+                            quote! {
+                                if #is_unfilled {
+                                    unfilled.push(struct_patch::__private::prefixed(prefix, #ident_str));
+                                }
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            quote! {
+                /// Check all the fields are filled, the error lists the fields still empty with
+                /// the fields of the nested structs
+                pub fn ensure_filled(&self) -> core::result::Result<(), struct_patch::error::Unfilled> {
+                    let mut fields = struct_patch::__private::Vec::new();
+                    self.__struct_patch_unfilled("", &mut fields);
+                    if fields.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(struct_patch::error::Unfilled { fields })
+                    }
+                }
+
+                /// Apply a filler, and return the fields it filled, the fields of the nested
+                /// structs are joined to the nesting fields with dots
+                pub fn apply_report(
+                    &mut self,
+                    filler: #name #generics,
+                ) -> struct_patch::__private::Vec<struct_patch::__private::String> {
+                    let mut filled = struct_patch::__private::Vec::new();
+                    self.__struct_patch_unfilled("", &mut filled);
+                    struct_patch::traits::Filler::apply(self, filler);
+                    let mut unfilled = struct_patch::__private::Vec::new();
+                    self.__struct_patch_unfilled("", &mut unfilled);
+                    filled.retain(|f| !unfilled.contains(f));
+                    filled
+                }
+
+                #[doc(hidden)]
+                #[allow(unused_variables, clippy::ptr_arg)]
+                pub fn __struct_patch_unfilled(
+                    &self,
+                    prefix: &str,
+                    unfilled: &mut struct_patch::__private::Vec<struct_patch::__private::String>,
+                ) {
+                    #(#unfilled_paths)*
+                }
+            }
+        };
+        #[cfg(not(feature = "alloc"))]
+        let ensure_filled = quote!();
+
+        quote! {
+            #[automatically_derived]
+            impl #generics #struct_name #generics #where_clause {
+                /// The names of the fields which are still empty, a nested field is listed when
+                /// any of its fields is empty
+                pub fn unfilled_fields(&self) -> impl Iterator<Item = &'static str> {
+                    let fields: [(&'static str, bool); #field_count] = [#(#unfilled_checks),*];
+                    core::iter::IntoIterator::into_iter(fields)
+                        .filter(|(_, unfilled)| *unfilled)
+                        .map(|(name, _)| name)
+                }
+
+                #ensure_filled
+            }
+        }
+    }
+
     /// Parse the filler struct
-    pub fn from_ast(
-        DeriveInput {
+    pub fn from_ast(input: syn::DeriveInput) -> Result<Filler> {
+        // The patch of the same struct is parsed from the whole input when it is asked
+        let patch_input = input.clone();
+        let DeriveInput {
             ident,
             data,
             generics,
             attrs,
             vis,
-        }: syn::DeriveInput,
-    ) -> Result<Filler> {
+        } = input;
         let original_fields = if let syn::Data::Struct(syn::DataStruct { fields, .. }) = data {
             fields
         } else {
@@ -285,8 +885,10 @@ impl Filler {
             ));
         };
 
+        let mut name = None;
         let mut attributes = vec![];
         let mut fields = vec![];
+        let mut with_patch = false;
 
         for attr in attrs {
             if attr.path().to_string().as_str() != FILLER {
@@ -302,6 +904,16 @@ impl Filler {
             attr.parse_nested_meta(|meta| {
                 let path = meta.path.to_string();
                 match path.as_str() {
+                    NAME => {
+                        // #[filler(name = "FillerStruct")]
+                        if let Some(lit) = crate::get_lit_str(path, &meta)? {
+                            if name.is_some() {
+                                return Err(meta
+                                    .error("The name attribute can't be defined more than once"));
+                            }
+                            name = Some(lit.parse()?);
+                        }
+                    }
                     ATTRIBUTE => {
                         // #[filler(attribute(derive(Deserialize)))]
                         // #[filler(attribute(derive(Deserialize, Debug), serde(rename = "foo"))]
@@ -310,6 +922,10 @@ impl Filler {
                         let attribute: TokenStream = content.parse()?;
                         attributes.push(attribute);
                     }
+                    PATCH => {
+                        // #[filler(patch)]
+                        with_patch = true;
+                    }
                     _ => {
                         return Err(meta.error(format_args!(
                             "unknown filler container attribute `{}`",
@@ -326,9 +942,19 @@ impl Filler {
                 fields.push(f);
             }
         }
-        let ts = TokenStream::from_str(&format!("{}Filler", &ident,)).unwrap();
-        let lit = LitStr::new(&ts.to_string(), Span::call_site());
-        let filler_struct_name = lit.parse()?;
+        let filler_struct_name = if let Some(name) = name {
+            name
+        } else {
+            let ts = TokenStream::from_str(&format!("{}Filler", &ident,)).unwrap();
+            let lit = LitStr::new(&ts.to_string(), Span::call_site());
+            lit.parse()?
+        };
+
+        let patch = if with_patch {
+            Some(crate::patch::Patch::from_ast(patch_input)?)
+        } else {
+            None
+        };
 
         Ok(Filler {
             visibility: vis,
@@ -337,11 +963,84 @@ impl Filler {
             generics,
             attributes,
             fields,
+            patch,
         })
     }
 }
 
 impl Field {
+    /// Generate the value of the field in the merged filler, the value of `other` is kept when it
+    /// is not empty, as the later patch wins when patches are merged
+    #[cfg(feature = "merge")]
+    fn merge_token_stream(&self) -> TokenStream {
+        let ident = &self.ident;
+        match &self.fty {
+            FillerType::Option => quote!(other.#ident.or(self.#ident)),
+            FillerType::Extendable => quote! {
+                if other.#ident.is_empty() { self.#ident } else { other.#ident }
+            },
+            FillerType::Fillable => quote! {
+                if struct_patch::traits::Fillable::is_empty(&other.#ident) {
+                    self.#ident
+                } else {
+                    other.#ident
+                }
+            },
+            FillerType::MergeKeys => quote! {
+                {
+                    let mut merged = other.#ident;
+                    for (k, v) in self.#ident {
+                        merged.entry(k).or_insert(v);
+                    }
+                    merged
+                }
+            },
+            FillerType::MergeMembers => quote! {
+                {
+                    let mut merged = other.#ident;
+                    merged.extend(self.#ident);
+                    merged
+                }
+            },
+            FillerType::NativeValue(empty) => {
+                let is_empty = empty.is_empty_token_stream(quote!(other.#ident));
+                quote! {
+                    if #is_empty { self.#ident } else { other.#ident }
+                }
+            }
+            #[cfg(feature = "nesting")]
+            FillerType::Nesting(_) => {
+                quote!(struct_patch::traits::Merge::merge(self.#ident, other.#ident))
+            }
+            #[cfg(feature = "nesting")]
+            FillerType::OptionNesting(_) => quote! {
+                match (self.#ident, other.#ident) {
+                    (Some(a), Some(b)) => Some(struct_patch::traits::Merge::merge(a, b)),
+                    (a, b) => b.or(a),
+                }
+            },
+        }
+    }
+
+    /// Generate the expression checking the field of the struct is still empty
+    fn unfilled_token_stream(&self) -> TokenStream {
+        let ident = &self.ident;
+        match &self.fty {
+            FillerType::Option => quote!(self.#ident.is_none()),
+            FillerType::Extendable | FillerType::MergeKeys | FillerType::MergeMembers => {
+                quote!(self.#ident.is_empty())
+            }
+            FillerType::Fillable => {
+                quote!(struct_patch::traits::Fillable::is_empty(&self.#ident))
+            }
+            FillerType::NativeValue(empty) => empty.is_empty_token_stream(quote!(self.#ident)),
+            #[cfg(feature = "nesting")]
+            FillerType::Nesting(_) | FillerType::OptionNesting(_) => {
+                unreachable!("the nesting fields are checked by the nested structs")
+            }
+        }
+    }
+
     /// Generate the token stream for the Filler struct fields
     pub fn to_token_stream(&self) -> Result<TokenStream> {
         let Field {
@@ -378,9 +1077,16 @@ impl Field {
         }: syn::Field,
     ) -> Result<Option<Field>> {
         let mut fty = filler_type(&ty);
+        #[cfg(feature = "schemars")]
+        let doc = crate::doc_text(&attrs);
         let mut attributes = vec![];
         #[cfg(feature = "op")]
         let mut addable = Addable::Disable;
+        #[cfg(feature = "nesting")]
+        let mut nesting = false;
+        let mut skip = false;
+        let mut field_type: Option<Type> = None;
+        let mut is_empty: Option<syn::Path> = None;
 
         for attr in attrs {
             if attr.path().to_string().as_str() != FILLER {
@@ -396,6 +1102,15 @@ impl Field {
             attr.parse_nested_meta(|meta| {
                 let path = meta.path.to_string();
                 match path.as_str() {
+                    SKIP => {
+                        // #[filler(skip)]
+                        skip = true;
+                    }
+                    NAME => {
+                        // #[filler(name = "ItemFiller")]
+                        let expr: LitStr = meta.value()?.parse()?;
+                        field_type = Some(expr.parse()?)
+                    }
                     ATTRIBUTE => {
                         // #[filler(attribute(serde(alias = "my-field")))]
                         let content;
@@ -409,20 +1124,59 @@ impl Field {
                             return Err(meta
                                 .error("The field is already the field of filler, we can't defined more than once"));
                         }
-                        fty = Some(FillerType::Extendable(none_option_filler_type(&ty)));
+                        fty = Some(FillerType::Extendable);
                     }
-                    EMPTY_VALUE => {
-                        // #[filler(empty_value=some value)]
+                    FILLABLE => {
+                        // #[filler(fillable)]
                         if fty.is_some() {
                             return Err(meta
                                 .error("The field is already the field of filler, we can't defined more than once"));
                         }
-                        if let Some(lit) = crate::get_lit(path, &meta)? {
-                            fty = Some(FillerType::NativeValue(lit));
+                        fty = Some(FillerType::Fillable);
+                    }
+                    MERGE_KEYS | MERGE_MEMBERS => {
+                        // #[filler(merge_keys)] or #[filler(merge_members)]
+                        if !matches!(fty, None | Some(FillerType::Extendable | FillerType::Fillable)) {
+                            return Err(meta.error(format_args!(
+                                "`{}` only works on the maps or the sets, and can't be combined with the other filler types",
+                                path
+                            )));
+                        }
+                        fty = Some(if path == MERGE_KEYS {
+                            FillerType::MergeKeys
                         } else {
+                            FillerType::MergeMembers
+                        });
+                    }
+                    EMPTY_VALUE => {
+                        // #[filler(empty_value=some value)]
+                        if fty.is_some() {
                             return Err(meta
-                                .error("empty_value needs a clear value to define empty"));
+                                .error("The field is already the field of filler, we can't defined more than once"));
                         }
+                        fty = Some(FillerType::NativeValue(crate::EmptyValue::new(
+                            meta.value()?.parse()?,
+                        )));
+                    }
+                    IS_EMPTY => {
+                        // #[filler(is_empty = path::fn)]
+                        if is_empty.is_some() {
+                            return Err(meta.error(
+                                "The is_empty attribute can't be defined more than once",
+                            ));
+                        }
+                        is_empty = Some(meta.value()?.parse()?);
+                    }
+                    #[cfg(feature = "nesting")]
+                    NESTING => {
+                        // #[filler(nesting)]
+                        nesting = true;
+                    }
+                    #[cfg(not(feature = "nesting"))]
+                    NESTING => {
+                        return Err(
+                            meta.error("#[filler(nesting)] only work with `nesting` feature")
+                        );
                     }
                     #[cfg(feature = "op")]
                     ADDABLE => {
@@ -446,6 +1200,65 @@ impl Field {
                 }
                 Ok(())
             })?;
+            if skip {
+                return Ok(None);
+            }
+        }
+
+        if let Some(path) = is_empty {
+            if let Some(FillerType::NativeValue(empty)) = &mut fty {
+                empty.is_empty = Some(path);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "`is_empty` needs `empty_value` to define the empty value",
+                ));
+            }
+        }
+
+        // The renamed field is filled by the filler of the given name
+        #[cfg(feature = "nesting")]
+        let (fty, ty) = if nesting || field_type.is_some() {
+            match fty {
+                None => {
+                    let filler_ty = match field_type {
+                        Some(filler_ty) => filler_ty,
+                        None => nested_filler_type(&ty)?,
+                    };
+                    (Some(FillerType::Nesting(ty)), filler_ty)
+                }
+                Some(FillerType::Option) => {
+                    let inner = crate::option_inner(&ty).cloned().ok_or_else(|| {
+                        syn::Error::new_spanned(
+                            &ty,
+                            "`nesting` needs `Option` with one type argument",
+                        )
+                    })?;
+                    let filler_ty = match field_type {
+                        Some(filler_ty) => filler_ty,
+                        None => nested_filler_type(&inner)?,
+                    };
+                    (
+                        Some(FillerType::OptionNesting(inner)),
+                        syn::parse_quote!(Option<#filler_ty>),
+                    )
+                }
+                Some(_) => {
+                    return Err(syn::Error::new_spanned(
+                        &ty,
+                        "`nesting` can not be combined with `extendable` or `empty_value`",
+                    ));
+                }
+            }
+        } else {
+            (fty, ty)
+        };
+        #[cfg(not(feature = "nesting"))]
+        if let Some(filler_ty) = field_type {
+            return Err(syn::Error::new_spanned(
+                filler_ty,
+                "#[filler(name = \"...\")] on a field only work with `nesting` feature",
+            ));
         }
 
         Ok(fty.map(|fty| Field {
@@ -455,6 +1268,8 @@ impl Field {
             fty,
             #[cfg(feature = "op")]
             addable,
+            #[cfg(feature = "schemars")]
+            doc,
         }))
     }
 }
@@ -469,38 +1284,56 @@ impl ToStr for syn::Path {
     }
 }
 
+/// The filler type of the field types known by their paths, the collections of `std` and `alloc`
+/// are extendable whatever the features are, and the other types implementing `Fillable` opt in
+/// with `#[filler(fillable)]`.  Only the bare names and the full paths in `std`, `alloc` or `core`
+/// are known, so a `heapless::Vec` or a `Vec` of the user is not taken as one of them.
 fn filler_type(ty: &Type) -> Option<FillerType> {
-    if let Type::Path(type_path) = ty {
-        let segments = &type_path.path.segments;
-        if segments.len() == 1 && segments[0].ident == "Option" {
-            if let syn::PathArguments::AngleBracketed(args) = &segments[0].arguments {
-                if args.args.len() == 1 {
-                    return Some(FillerType::Option);
-                }
-            }
-        } else if segments.len() == 1 && segments[0].ident == "Vec"
-            || segments[0].ident == "VecDeque"
-            || segments[0].ident == "LinkedList"
-            || segments[0].ident == "HashMap"
-            || segments[0].ident == "BTreeMap"
-            || segments[0].ident == "HashSet"
-            || segments[0].ident == "BTreeSet"
-            || segments[0].ident == "BinaryHeap"
+    let type_path = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path,
+        _ => return None,
+    };
+    let segments = type_path.path.segments.iter().collect::<Vec<_>>();
+    let (segment, module) = match segments.as_slice() {
+        [segment] if type_path.path.leading_colon.is_none() => (segment, None),
+        [root, module, segment]
+            if matches!(root.ident.to_string().as_str(), "std" | "alloc" | "core") =>
         {
-            if let syn::PathArguments::AngleBracketed(args) = &segments[0].arguments {
-                if args.args.len() == 1 {
-                    return Some(FillerType::Extendable(segments[0].ident.clone()));
-                }
-            }
+            (segment, Some(module.ident.to_string()))
         }
+        _ => return None,
+    };
+    if !matches!(segment.arguments, syn::PathArguments::AngleBracketed(_)) {
+        return None;
+    }
+    let (fty, expected_module) = match segment.ident.to_string().as_str() {
+        "Option" => (FillerType::Option, "option"),
+        "Vec" => (FillerType::Extendable, "vec"),
+        "VecDeque" | "LinkedList" | "HashMap" | "BTreeMap" | "HashSet" | "BTreeSet"
+        | "BinaryHeap" => (FillerType::Extendable, "collections"),
+        _ => return None,
+    };
+    match module {
+        Some(module) if module != expected_module => None,
+        _ => Some(fty),
     }
-    None
 }
 
-fn none_option_filler_type(ty: &Type) -> Ident {
+/// The filler struct of the nested struct, `Inner` becomes `InnerFiller`
+#[cfg(feature = "nesting")]
+fn nested_filler_type(ty: &Type) -> Result<Type> {
     if let Type::Path(type_path) = ty {
-        type_path.path.segments[0].ident.clone()
-    } else {
-        panic!("#[filler(extendable)] should use on a type")
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.arguments.is_empty() {
+                let mut filler_path = type_path.clone();
+                let last = filler_path.path.segments.last_mut().unwrap();
+                last.ident = Ident::new(&format!("{}Filler", segment.ident), segment.ident.span());
+                return Ok(Type::Path(filler_path));
+            }
+        }
     }
+    Err(syn::Error::new_spanned(
+        ty,
+        "`nesting` needs the field to be a struct deriving `Filler`, or the `Option` of it",
+    ))
 }
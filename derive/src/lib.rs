@@ -2,6 +2,7 @@ extern crate proc_macro;
 #[cfg(feature = "catalyst")]
 mod catalyst;
 mod filler;
+mod literal;
 mod patch;
 #[cfg(feature = "catalyst")]
 mod substrate;
@@ -9,6 +10,7 @@ mod substrate;
 #[cfg(feature = "catalyst")]
 use catalyst::Catalyst;
 use filler::Filler;
+use literal::PatchLiteral;
 use patch::Patch;
 #[cfg(feature = "catalyst")]
 use substrate::Substrate;
@@ -24,6 +26,71 @@ pub(crate) enum Addable {
     AddFn(proc_macro2::Ident),
 }
 
+/// The value of a field meaning it is empty, `empty_value = ...`, which is any expression, and the
+/// optional `is_empty = path::fn` predicate checking the emptiness instead of `==`
+pub(crate) struct EmptyValue {
+    value: Box<syn::Expr>,
+    pub(crate) is_empty: Option<syn::Path>,
+    /// The value as written, documented in the JSON schema
+    #[cfg(feature = "schemars")]
+    text: String,
+}
+
+impl EmptyValue {
+    pub(crate) fn new(value: syn::Expr) -> Self {
+        #[cfg(feature = "schemars")]
+        let text = tokens_text(quote::ToTokens::to_token_stream(&value));
+        // The operators are kept together when the value is compared
+        let value = match value {
+            syn::Expr::Binary(_) | syn::Expr::Cast(_) | syn::Expr::Range(_) => {
+                syn::parse_quote!((#value))
+            }
+            value => value,
+        };
+        EmptyValue {
+            value: Box::new(value),
+            is_empty: None,
+            #[cfg(feature = "schemars")]
+            text,
+        }
+    }
+
+    /// Generate the expression checking the value is empty
+    pub(crate) fn is_empty_token_stream(
+        &self,
+        value: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        match &self.is_empty {
+            Some(is_empty) => {
+                quote::quote!(struct_patch::__private::is_empty(#is_empty, &#value))
+            }
+            None => {
+                let empty = &self.value;
+                quote::quote!(#value == #empty)
+            }
+        }
+    }
+}
+
+impl quote::ToTokens for EmptyValue {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        self.value.to_tokens(tokens)
+    }
+}
+
+/// Generate the expressions checking the fields of the receiver are empty
+pub(crate) fn empty_checks(
+    receiver: proc_macro2::TokenStream,
+    names: &[Option<&proc_macro2::Ident>],
+    empty_values: &[&EmptyValue],
+) -> Vec<proc_macro2::TokenStream> {
+    names
+        .iter()
+        .zip(empty_values)
+        .map(|(name, empty)| empty.is_empty_token_stream(quote::quote!(#receiver.#name)))
+        .collect()
+}
+
 #[proc_macro_derive(Patch, attributes(patch))]
 pub fn derive_patch(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     Patch::from_ast(syn::parse_macro_input!(item as syn::DeriveInput))
@@ -42,6 +109,13 @@ pub fn derive_filler(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
         .into()
 }
 
+#[proc_macro]
+pub fn patch(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    syn::parse_macro_input!(item as PatchLiteral)
+        .to_token_stream()
+        .into()
+}
+
 #[cfg(feature = "catalyst")]
 #[proc_macro_derive(Substrate, attributes(substrate))]
 pub fn derive_substrate(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -62,22 +136,428 @@ pub fn derive_catalyst(item: proc_macro::TokenStream) -> proc_macro::TokenStream
         .into()
 }
 
-fn get_lit(attr_name: String, meta: &ParseNestedMeta) -> syn::Result<Option<syn::Lit>> {
-    let expr: syn::Expr = meta.value()?.parse()?;
-    let mut value = &expr;
-    while let syn::Expr::Group(e) = value {
-        value = &e.expr;
+/// The enum variant for a field, `field_int` becomes `FieldInt`
+#[cfg(feature = "field")]
+pub(crate) fn variant_ident(ident: &proc_macro2::Ident) -> proc_macro2::Ident {
+    let name = ident.to_string();
+    let mut variant = String::with_capacity(name.len());
+    for part in name.trim_start_matches("r#").split('_') {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            variant.extend(c.to_uppercase());
+            variant.push_str(chars.as_str());
+        }
     }
-    if let syn::Expr::Lit(syn::ExprLit { lit, .. }) = value {
-        Ok(Some(lit.clone()))
+    proc_macro2::Ident::new(&variant, ident.span())
+}
+
+/// The command line option for a field, `field_int` becomes `field-int`
+#[cfg(feature = "clap")]
+pub(crate) fn kebab_name(ident: &proc_macro2::Ident) -> String {
+    ident.to_string().trim_start_matches("r#").replace('_', "-")
+}
+
+/// The first paragraph of the doc comments, joined into one line
+#[cfg(any(feature = "clap", feature = "schemars"))]
+pub(crate) fn doc_text(attrs: &[syn::Attribute]) -> Option<String> {
+    let mut lines = Vec::new();
+    for attr in attrs {
+        if !attr.path().is_ident("doc") {
+            continue;
+        }
+        if let syn::Meta::NameValue(syn::MetaNameValue {
+            value:
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(lit),
+                    ..
+                }),
+            ..
+        }) = &attr.meta
+        {
+            let line = lit.value();
+            let line = line.trim();
+            if line.is_empty() {
+                if lines.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            lines.push(line.to_string());
+        }
+    }
+    if lines.is_empty() {
+        None
     } else {
-        Err(Error::new(
-            expr.span(),
-            format!(
-                "expected serde {} attribute to be lit: `{} = \"...\"`",
-                attr_name, attr_name
-            ),
-        ))
+        Some(lines.join(" "))
+    }
+}
+
+/// The text of the tokens as they are usually written, without the spaces `to_string` puts between
+/// all of them, `u32::MAX` rather than `u32 :: MAX`
+#[cfg(feature = "schemars")]
+fn tokens_text(tokens: proc_macro2::TokenStream) -> String {
+    use proc_macro2::{Delimiter, Spacing, TokenTree};
+
+    // What the previous token needs before the next one
+    #[derive(PartialEq)]
+    enum Prev {
+        /// Nothing, at the start, after a path separator or a unary operator
+        Glued,
+        /// A space before the next word, an ident, a literal or a group
+        Word,
+        /// A space before the next token, after a binary operator, `,` or `;`
+        Space,
+    }
+
+    let mut text = String::new();
+    let mut prev = Prev::Glued;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                if prev != Prev::Glued {
+                    text.push(' ');
+                }
+                text.push_str(&token.to_string());
+                prev = Prev::Word;
+            }
+            TokenTree::Group(group) => {
+                let inner = tokens_text(group.stream());
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                // The calls and the indexes are glued to the callee
+                if prev == Prev::Space
+                    || (prev == Prev::Word && group.delimiter() == Delimiter::Brace)
+                {
+                    text.push(' ');
+                }
+                text.push_str(open);
+                text.push_str(&inner);
+                text.push_str(close);
+                prev = Prev::Word;
+            }
+            TokenTree::Punct(punct) => {
+                let mut op = punct.as_char().to_string();
+                let mut spacing = punct.spacing();
+                while spacing == Spacing::Joint {
+                    match tokens.peek() {
+                        Some(TokenTree::Punct(next)) => {
+                            op.push(next.as_char());
+                            spacing = next.spacing();
+                            tokens.next();
+                        }
+                        _ => break,
+                    }
+                }
+                match op.as_str() {
+                    "::" | "." | ".." | "..=" | "'" => {
+                        text.push_str(&op);
+                        prev = Prev::Glued;
+                    }
+                    "," | ";" | ":" => {
+                        text.push_str(&op);
+                        prev = Prev::Space;
+                    }
+                    "-" | "!" | "&" | "*" if prev != Prev::Word => {
+                        if prev == Prev::Space {
+                            text.push(' ');
+                        }
+                        text.push_str(&op);
+                        prev = Prev::Glued;
+                    }
+                    _ => {
+                        if prev != Prev::Glued {
+                            text.push(' ');
+                        }
+                        text.push_str(&op);
+                        prev = Prev::Space;
+                    }
+                }
+            }
+        }
+    }
+    text
+}
+
+/// Parse the `serde(...)` attributes passed to the generated struct, which change the properties
+/// of its JSON schema, and reject the `schemars(...)` ones, as the `JsonSchema` impl is generated
+/// in place of the derive
+#[cfg(feature = "schemars")]
+fn parse_serde_attributes(
+    attributes: &[proc_macro2::TokenStream],
+    mut f: impl FnMut(&str, &ParseNestedMeta) -> syn::Result<bool>,
+) -> syn::Result<()> {
+    for attribute in attributes {
+        let list = match syn::parse2::<syn::Meta>(attribute.clone()) {
+            Ok(syn::Meta::List(list)) => list,
+            _ => continue,
+        };
+        if list.path.is_ident("schemars") {
+            return Err(Error::new_spanned(
+                attribute,
+                "`schemars` attributes are not supported, the `JsonSchema` impl of the patch and \
+                the filler is generated in place of the derive",
+            ));
+        }
+        if !list.path.is_ident("serde") {
+            continue;
+        }
+        list.parse_nested_meta(|meta| {
+            let path = quote::ToTokens::to_token_stream(&meta.path).to_string();
+            if path == "flatten" {
+                return Err(meta.error(
+                    "`serde(flatten)` is not supported by the generated `JsonSchema` impl",
+                ));
+            }
+            if f(&path, &meta)? {
+                return Ok(());
+            }
+            // The other attributes do not change the properties
+            if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            } else if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<proc_macro2::TokenStream>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+/// The name of `rename = "..."`, or of `rename(deserialize = "...")` as the patches and the
+/// fillers are deserialized
+#[cfg(feature = "schemars")]
+fn serde_name(meta: &ParseNestedMeta) -> syn::Result<Option<String>> {
+    if meta.input.peek(syn::Token![=]) {
+        return Ok(Some(meta.value()?.parse::<syn::LitStr>()?.value()));
+    }
+    let mut name = None;
+    meta.parse_nested_meta(|meta| {
+        let value = meta.value()?.parse::<syn::LitStr>()?.value();
+        if meta.path.is_ident("deserialize") {
+            name = Some(value);
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// The `rename_all` rule of the `serde` attributes of the container
+#[cfg(feature = "schemars")]
+pub(crate) fn schema_rename_all(
+    attributes: &[proc_macro2::TokenStream],
+) -> syn::Result<Option<String>> {
+    let mut rename_all = None;
+    parse_serde_attributes(attributes, |path, meta| {
+        if path != "rename_all" {
+            return Ok(false);
+        }
+        if let Some(rule) = serde_name(meta)? {
+            // Checked here so the error points at the attribute
+            rename_field("", &rule).map_err(|e| meta.error(e))?;
+            rename_all = Some(rule);
+        }
+        Ok(true)
+    })?;
+    Ok(rename_all)
+}
+
+/// The name of the property of a field, following its `serde` attributes and the `rename_all`
+/// rule of the container, `None` if the field is skipped
+#[cfg(feature = "schemars")]
+pub(crate) fn schema_property_name(
+    ident: &proc_macro2::Ident,
+    attributes: &[proc_macro2::TokenStream],
+    rename_all: Option<&str>,
+) -> syn::Result<Option<String>> {
+    let mut rename = None;
+    let mut skip = false;
+    parse_serde_attributes(attributes, |path, meta| match path {
+        "rename" => {
+            rename = serde_name(meta)?.or(rename.take());
+            Ok(true)
+        }
+        "skip" | "skip_deserializing" => {
+            skip = true;
+            Ok(true)
+        }
+        _ => Ok(false),
+    })?;
+    if skip {
+        return Ok(None);
+    }
+    let name = ident.to_string().trim_start_matches("r#").to_string();
+    Ok(Some(match (rename, rename_all) {
+        (Some(rename), _) => rename,
+        (None, Some(rule)) => rename_field(&name, rule).map_err(|e| Error::new(ident.span(), e))?,
+        (None, None) => name,
+    }))
+}
+
+/// Rename a snake case field name with a `serde(rename_all = "...")` rule
+#[cfg(feature = "schemars")]
+fn rename_field(name: &str, rule: &str) -> Result<String, String> {
+    let pascal = || {
+        name.split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            })
+            .collect::<String>()
+    };
+    Ok(match rule {
+        "lowercase" | "snake_case" => name.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|c| c.to_lowercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.to_uppercase().replace('_', "-"),
+        _ => return Err(format!("unknown `rename_all` rule `{}`", rule)),
+    })
+}
+
+/// Generate the statement adding the schema of a field to the `properties` of a JSON schema, the
+/// description documents the empty value of the field if any
+#[cfg(feature = "schemars")]
+pub(crate) fn schema_property(
+    name: String,
+    ty: proc_macro2::TokenStream,
+    doc: Option<&String>,
+    empty_value: Option<&EmptyValue>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let description = match (doc, empty_value) {
+        (Some(doc), Some(empty)) => Some(format!(
+            "{} `{}` leaves the field unchanged.",
+            doc, empty.text
+        )),
+        (None, Some(empty)) => Some(format!("`{}` leaves the field unchanged.", empty.text)),
+        (Some(doc), None) => Some(doc.clone()),
+        (None, None) => None,
+    }
+    .map(|d| quote::quote! { property.insert("description".into(), #d.into()); });
+    let property = quote::quote! {
+        {
+            let mut property = generator.subschema_for::<#ty>();
+            #description
+            properties.insert(#name.into(), property.into());
+        }
+    };
+    (ty, property)
+}
+
+/// Generate the `JsonSchema` impl of an object whose properties are all optional, replacing the
+/// `JsonSchema` derive requested for the patch or filler struct
+#[cfg(feature = "schemars")]
+pub(crate) fn schema_impl(
+    name: &proc_macro2::Ident,
+    generics: &syn::Generics,
+    properties: Vec<(proc_macro2::TokenStream, proc_macro2::TokenStream)>,
+) -> proc_macro2::TokenStream {
+    let mut schema_generics = generics.clone();
+    let where_clause = schema_generics.make_where_clause();
+    for (ty, _) in properties.iter() {
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(#ty: struct_patch::__private::schemars::JsonSchema));
+    }
+    let properties = properties.into_iter().map(|(_, p)| p);
+    let name_str = name.to_string();
+    quote::quote! {
+        #[automatically_derived]
+        impl #generics struct_patch::__private::schemars::JsonSchema for #name #generics #where_clause {
+            fn schema_name() -> struct_patch::__private::Cow<'static, str> {
+                #name_str.into()
+            }
+
+            fn schema_id() -> struct_patch::__private::Cow<'static, str> {
+                concat!(module_path!(), "::", #name_str).into()
+            }
+
+            fn json_schema(
+                generator: &mut struct_patch::__private::schemars::SchemaGenerator,
+            ) -> struct_patch::__private::schemars::Schema {
+                let mut properties = struct_patch::__private::serde_json::Map::new();
+                #(#properties)*
+                let mut schema = struct_patch::__private::serde_json::Map::new();
+                schema.insert("type".into(), "object".into());
+                schema.insert("properties".into(), properties.into());
+                schema.into()
+            }
+        }
+    }
+}
+
+/// The `T` of `Option<T>`
+#[cfg(any(feature = "clap", feature = "nesting"))]
+pub(crate) fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let p = if let syn::Type::Path(p) = ty {
+        p
+    } else {
+        return None;
+    };
+    let segment = p.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The traits of the `derive(...)` attribute, `None` for other attributes
+fn derived_traits(
+    attribute: &proc_macro2::TokenStream,
+) -> Option<syn::punctuated::Punctuated<syn::Path, syn::Token![,]>> {
+    match syn::parse2::<syn::Meta>(attribute.clone()) {
+        Ok(syn::Meta::List(list)) if list.path.is_ident("derive") => list
+            .parse_args_with(syn::punctuated::Punctuated::parse_terminated)
+            .ok(),
+        _ => None,
+    }
+}
+
+/// Whether the attribute is a `derive(...)` of the trait
+pub(crate) fn has_derive(attribute: &proc_macro2::TokenStream, trait_name: &str) -> bool {
+    derived_traits(attribute).map_or(false, |paths| {
+        paths
+            .iter()
+            .any(|p| p.segments.last().map_or(false, |s| s.ident == trait_name))
+    })
+}
+
+/// Remove the trait from the `derive(...)` attribute, other attributes are kept as they are
+pub(crate) fn remove_derive(
+    attribute: &proc_macro2::TokenStream,
+    trait_name: &str,
+) -> proc_macro2::TokenStream {
+    match derived_traits(attribute) {
+        Some(paths) => {
+            let paths = paths
+                .into_iter()
+                .filter(|p| !p.segments.last().map_or(false, |s| s.ident == trait_name));
+            quote::quote!(derive(#(#paths),*))
+        }
+        None => attribute.clone(),
     }
 }
 
@@ -0,0 +1,97 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{braced, Expr, Path, Result, Token};
+
+/// A sparse patch literal, `Item { field_int: 7, inner: { inner_int: 100 } }`
+pub(crate) struct PatchLiteral {
+    struct_path: Path,
+    fields: Punctuated<FieldValue, Token![,]>,
+}
+
+struct FieldValue {
+    ident: Ident,
+    value: Value,
+}
+
+enum Value {
+    /// A value for the field, without the `Option` wrapper of the patch
+    Expr(Expr),
+    /// The fields of a `#[patch(nesting)]` field
+    Nested(Punctuated<FieldValue, Token![,]>),
+}
+
+/// The hidden setter generated by the `Patch` derive for the field
+pub(crate) fn setter_ident(ident: &Ident) -> Ident {
+    format_ident!("__struct_patch_set_{}", ident)
+}
+
+impl PatchLiteral {
+    /// Generate the expression building the patch
+    pub fn to_token_stream(&self) -> TokenStream {
+        let PatchLiteral {
+            struct_path,
+            fields,
+        } = self;
+        let patch = Ident::new("__struct_patch", proc_macro2::Span::mixed_site());
+        let assignments = assignments(quote!(#patch), fields);
+
+        quote! {
+            {
+                let mut #patch = <#struct_path>::__struct_patch_empty();
+                #(#assignments)*
+                #patch
+            }
+        }
+    }
+}
+
+fn assignments(
+    target: TokenStream,
+    fields: &Punctuated<FieldValue, Token![,]>,
+) -> Vec<TokenStream> {
+    let mut tokens = Vec::new();
+    for FieldValue { ident, value } in fields {
+        match value {
+            Value::Expr(expr) => {
+                let setter = setter_ident(ident);
+                tokens.push(quote! {
+                    #target.#setter(#expr);
+                });
+            }
+            Value::Nested(fields) => {
+                tokens.extend(assignments(quote!(#target.#ident), fields));
+            }
+        }
+    }
+    tokens
+}
+
+impl Parse for PatchLiteral {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let struct_path: Path = input.parse()?;
+        let content;
+        braced!(content in input);
+        let fields = content.parse_terminated(FieldValue::parse, Token![,])?;
+        Ok(PatchLiteral {
+            struct_path,
+            fields,
+        })
+    }
+}
+
+impl Parse for FieldValue {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident: Ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let value = if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            Value::Nested(content.parse_terminated(FieldValue::parse, Token![,])?)
+        } else {
+            Value::Expr(input.parse()?)
+        };
+        Ok(FieldValue { ident, value })
+    }
+}
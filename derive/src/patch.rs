@@ -4,7 +4,7 @@ use quote::{quote, ToTokens};
 use std::str::FromStr;
 #[cfg(not(feature = "op"))]
 use syn::spanned::Spanned;
-use syn::{parenthesized, DeriveInput, Lit, LitStr, Result, Type};
+use syn::{parenthesized, DeriveInput, LitStr, Result, Type};
 
 #[cfg(feature = "op")]
 use crate::Addable;
@@ -17,21 +17,45 @@ const ADDABLE: &str = "addable";
 const ADD: &str = "add";
 const NESTING: &str = "nesting";
 const EMPTY_VALUE: &str = "empty_value";
+const IS_EMPTY: &str = "is_empty";
 const SKIP_WRAP: &str = "skip_wrap";
+const ON_CHANGE: &str = "on_change";
+const AFTER_APPLY: &str = "after_apply";
+const CLI: &str = "cli";
+const AUDIT: &str = "audit";
+const REDACT: &str = "redact";
+const READONLY: &str = "readonly";
+const GROUP: &str = "group";
+const COLUMN: &str = "column";
+const SQL: &str = "sql";
+const MASK_TO_PATCH: &str = "mask_to_patch";
 
 pub(crate) struct Patch {
     visibility: syn::Visibility,
     struct_name: Ident,
     patch_struct_name: Ident,
+    #[cfg(feature = "field")]
+    field_enum_name: Ident,
+    #[cfg(feature = "change")]
+    change_enum_name: Ident,
     generics: syn::Generics,
     attributes: Vec<TokenStream>,
     fields: Vec<Field>,
+    after_apply: Option<syn::Path>,
+    #[cfg(feature = "clap")]
+    cli: bool,
+    #[cfg(feature = "audit")]
+    audit: bool,
+    #[cfg(feature = "sql")]
+    sql: bool,
+    #[cfg(feature = "alloc")]
+    mask_to_patch: bool,
 }
 
 enum SpecialAttr {
     None,
     /// Field uses an explicit sentinel value instead of `Option` wrapping.
-    EmptyValue(Lit),
+    EmptyValue(crate::EmptyValue),
     /// Field type is already `Option<T>`; `None` means "no change", `Some(v)` applies the value.
     SkipWrap,
 }
@@ -41,18 +65,31 @@ impl SpecialAttr {
         matches!(self, SpecialAttr::None)
     }
 
-    fn empty_value(&self) -> Option<&Lit> {
-        if let SpecialAttr::EmptyValue(lit) = self {
-            Some(lit)
+    fn empty_value(&self) -> Option<&crate::EmptyValue> {
+        if let SpecialAttr::EmptyValue(empty) = self {
+            Some(empty)
         } else {
             None
         }
     }
 }
 
+/// The shape of a field in the patch struct
+pub(crate) enum PatchShape {
+    /// `Option` of the field type
+    Wrapped,
+    /// The field type itself, with the expression checking `patch.field` is set
+    Unwrapped(TokenStream),
+    /// The patch of the nested struct
+    Nesting,
+}
+
 struct Field {
     ident: Option<Ident>,
     ty: Type,
+    /// The type of the field in the struct, which differs from `ty` for the renamed fields
+    #[cfg(feature = "alloc")]
+    struct_ty: Type,
     attributes: Vec<TokenStream>,
     retyped: bool,
     #[cfg(feature = "op")]
@@ -60,6 +97,19 @@ struct Field {
     #[cfg(feature = "nesting")]
     nesting: bool,
     special_attr: SpecialAttr,
+    on_change: Option<syn::Path>,
+    redact: bool,
+    readonly: bool,
+    /// The permission group needed to change the field, and the fields nested in it
+    group: Option<LitStr>,
+    /// The column name of the field in the `SET` clause, or the prefix of the columns of the
+    /// nesting field
+    #[cfg(feature = "sql")]
+    column: Option<LitStr>,
+    /// The first paragraph of the doc comment, used as the help text of the command line option
+    /// and the description in the JSON schema
+    #[cfg(any(feature = "clap", feature = "schemars"))]
+    doc: Option<String>,
 }
 
 impl Patch {
@@ -72,6 +122,8 @@ impl Patch {
             generics,
             attributes,
             fields,
+            after_apply,
+            ..
         } = self;
 
         let patch_struct_fields = fields
@@ -108,6 +160,12 @@ impl Patch {
             .iter()
             .filter_map(|f| f.special_attr.empty_value())
             .collect::<Vec<_>>();
+        #[cfg(feature = "status")]
+        let field_name_empty_checks = crate::empty_checks(
+            quote!(self),
+            &field_names_by_empty_value,
+            &field_name_empty_values,
+        );
 
         // Fields with `#[patch(skip_wrap)]` — the patch keeps the original
         // (already-`Option`) type, and `None` in the patch means "no change".
@@ -154,6 +212,13 @@ impl Patch {
             .filter(|f| f.retyped)
             .filter_map(|f| f.special_attr.empty_value())
             .collect::<Vec<_>>();
+        let renamed_field_empty_checks = |receiver| {
+            crate::empty_checks(
+                receiver,
+                &renamed_field_names_by_empty_value,
+                &renamed_field_name_empty_values,
+            )
+        };
 
         // Original fields
         #[cfg(not(feature = "nesting"))]
@@ -192,6 +257,17 @@ impl Patch {
             .filter(|f| !f.retyped && !f.nesting)
             .filter_map(|f| f.special_attr.empty_value())
             .collect::<Vec<_>>();
+        let original_field_empty_checks = |receiver| {
+            crate::empty_checks(
+                receiver,
+                &original_field_names_by_empty_value,
+                &original_field_name_empty_values,
+            )
+        };
+        #[cfg(any(feature = "merge", feature = "op"))]
+        let renamed_field_self_empty_checks = renamed_field_empty_checks(quote!(self));
+        #[cfg(any(feature = "merge", feature = "op"))]
+        let original_field_self_empty_checks = original_field_empty_checks(quote!(self));
 
         // Nesting fields
         #[cfg(not(feature = "nesting"))]
@@ -212,9 +288,24 @@ impl Patch {
             .map(|f| f.ty.clone())
             .collect::<Vec<_>>();
 
+        // The requested `Debug` is generated to hide the redacted fields, so it is not derived
+        let redacted = fields.iter().any(|f| f.redact)
+            && attributes.iter().any(|a| crate::has_derive(a, "Debug"));
+        // So is the requested `JsonSchema`, to make all the properties optional
+        #[cfg(feature = "schemars")]
+        let schema = attributes
+            .iter()
+            .any(|a| crate::has_derive(a, "JsonSchema"));
         let mapped_attributes = attributes
             .iter()
             .map(|a| {
+                let a = if redacted {
+                    crate::remove_derive(a, "Debug")
+                } else {
+                    a.clone()
+                };
+                #[cfg(feature = "schemars")]
+                let a = crate::remove_derive(&a, "JsonSchema");
                 quote! {
                     #[#a]
                 }
@@ -240,7 +331,7 @@ impl Patch {
                         }
                     )*
                     #(
-                        if self.#field_names_by_empty_value == #field_name_empty_values {
+                        if !(#field_name_empty_checks) {
                             return false
                         }
                     )*
@@ -261,6 +352,10 @@ impl Patch {
         #[cfg(not(feature = "status"))]
         let patch_status_impl = quote!();
 
+        #[cfg(feature = "merge")]
+        let renamed_field_other_empty_checks = renamed_field_empty_checks(quote!(other));
+        #[cfg(feature = "merge")]
+        let original_field_other_empty_checks = original_field_empty_checks(quote!(other));
         #[cfg(feature = "merge")]
         let patch_merge_impl = quote!(
             #[automatically_derived]
@@ -276,7 +371,7 @@ impl Patch {
                             },
                         )*
                         #(
-                            #renamed_field_names_by_empty_value: match (self.#renamed_field_names_by_empty_value == #renamed_field_name_empty_values, other.#renamed_field_names_by_empty_value == #renamed_field_name_empty_values) {
+                            #renamed_field_names_by_empty_value: match (#renamed_field_self_empty_checks, #renamed_field_other_empty_checks) {
                                 (false, false) => self.#renamed_field_names_by_empty_value.merge(other.#renamed_field_names_by_empty_value),
                                 (false, true) => self.#renamed_field_names_by_empty_value,
                                 (true, false) => other.#renamed_field_names_by_empty_value,
@@ -287,7 +382,7 @@ impl Patch {
                             #original_field_names: other.#original_field_names.or(self.#original_field_names),
                         )*
                         #(
-                            #original_field_names_by_empty_value: match (self.#original_field_names_by_empty_value == #original_field_name_empty_values, other.#original_field_names_by_empty_value == #original_field_name_empty_values) {
+                            #original_field_names_by_empty_value: match (#original_field_self_empty_checks, #original_field_other_empty_checks) {
                                 (false, false) => self.#original_field_names_by_empty_value.merge(other.#original_field_names_by_empty_value),
                                 (false, true) => self.#original_field_names_by_empty_value,
                                 (true, false) => other.#original_field_names_by_empty_value,
@@ -307,6 +402,10 @@ impl Patch {
         #[cfg(not(feature = "merge"))]
         let patch_merge_impl = quote!();
 
+        #[cfg(feature = "op")]
+        let renamed_field_rhs_empty_checks = renamed_field_empty_checks(quote!(rhs));
+        #[cfg(feature = "op")]
+        let original_field_rhs_empty_checks = original_field_empty_checks(quote!(rhs));
         #[cfg(feature = "op")]
         let addable_handles = fields
             .iter()
@@ -364,7 +463,7 @@ impl Patch {
                             },
                         )*
                         #(
-                            #renamed_field_names_by_empty_value: match (self.#renamed_field_names_by_empty_value == #renamed_field_name_empty_values, rhs.#renamed_field_names_by_empty_value == #renamed_field_name_empty_values) {
+                            #renamed_field_names_by_empty_value: match (#renamed_field_self_empty_checks, #renamed_field_rhs_empty_checks) {
                                 (false, false) => {
                                     let a = self.#renamed_field_names_by_empty_value;
                                     let b = rhs.#renamed_field_names_by_empty_value;
@@ -386,7 +485,7 @@ impl Patch {
                             },
                         )*
                         #(
-                            #original_field_names_by_empty_value: match (self.#original_field_names_by_empty_value == #original_field_name_empty_values , rhs.#original_field_names_by_empty_value == #original_field_name_empty_values) {
+                            #original_field_names_by_empty_value: match (#original_field_self_empty_checks, #original_field_rhs_empty_checks) {
                                 (false, false) => {
                                     let a = self.#original_field_names_by_empty_value;
                                     let b = rhs.#original_field_names_by_empty_value;
@@ -453,7 +552,7 @@ impl Patch {
                             },
                         )*
                         #(
-                            #renamed_field_names_by_empty_value: match (self.#renamed_field_names_by_empty_value == #renamed_field_name_empty_values, rhs.#renamed_field_names_by_empty_value == #renamed_field_name_empty_values) {
+                            #renamed_field_names_by_empty_value: match (#renamed_field_self_empty_checks, #renamed_field_rhs_empty_checks) {
                                 (false, false) => {
                                     let a = self.#renamed_field_names_by_empty_value;
                                     let b = rhs.#renamed_field_names_by_empty_value;
@@ -475,7 +574,7 @@ impl Patch {
                             },
                         )*
                         #(
-                            #original_field_names_by_empty_value: match (self.#original_field_names_by_empty_value == #original_field_name_empty_values , rhs.#original_field_names_by_empty_value == #original_field_name_empty_values) {
+                            #original_field_names_by_empty_value: match (#original_field_self_empty_checks, #original_field_rhs_empty_checks) {
                                 (false, false) => {
                                     let a = self.#original_field_names_by_empty_value;
                                     let b = rhs.#original_field_names_by_empty_value;
@@ -507,38 +606,63 @@ impl Patch {
         #[cfg(not(feature = "op"))]
         let op_impl = quote!();
 
+        // Assignments of the fields replaced as a whole, which call the `on_change` hook if any
+        let original_field_assigns = fields
+            .iter()
+            .filter(|f| !f.retyped && !f.is_nesting() && f.special_attr.is_empty())
+            .map(|f| f.assign_token_stream())
+            .collect::<Vec<_>>();
+        let original_field_assigns_by_empty_value = fields
+            .iter()
+            .filter(|f| {
+                !f.retyped && !f.is_nesting() && matches!(f.special_attr, SpecialAttr::EmptyValue(_))
+            })
+            .map(|f| f.assign_token_stream())
+            .collect::<Vec<_>>();
+        let skip_wrap_field_assigns = fields
+            .iter()
+            .filter(|f| matches!(f.special_attr, SpecialAttr::SkipWrap) && !f.is_nesting())
+            .map(|f| f.assign_token_stream())
+            .collect::<Vec<_>>();
+        let after_apply = after_apply.as_ref().map(|f| quote!(#f(self);));
+        let renamed_field_patch_empty_checks = renamed_field_empty_checks(quote!(patch));
+        let original_field_patch_empty_checks = original_field_empty_checks(quote!(patch));
+
         let patch_impl = quote! {
             #[automatically_derived]
             impl #generics struct_patch::traits::Patch< #name #generics > for #struct_name #generics #where_clause  {
                 fn apply(&mut self, patch: #name #generics) {
                     #(
                         if let Some(v) = patch.#renamed_field_names {
-                            self.#renamed_field_names.apply(v);
+                            struct_patch::traits::Patch::apply(&mut self.#renamed_field_names, v);
                         }
                     )*
                     #(
-                        if patch.#renamed_field_names_by_empty_value != #renamed_field_name_empty_values {
-                            self.#renamed_field_names_by_empty_value.apply(patch.#renamed_field_names_by_empty_value);
+                        if !(#renamed_field_patch_empty_checks) {
+                            struct_patch::traits::Patch::apply(&mut self.#renamed_field_names_by_empty_value, patch.#renamed_field_names_by_empty_value);
                         }
                     )*
                     #(
                         if let Some(v) = patch.#original_field_names {
-                            self.#original_field_names = v;
+                            #original_field_assigns
                         }
                     )*
                     #(
-                        if patch.#original_field_names_by_empty_value != #original_field_name_empty_values  {
-                            self.#original_field_names_by_empty_value = patch.#original_field_names_by_empty_value ;
+                        if !(#original_field_patch_empty_checks) {
+                            let v = patch.#original_field_names_by_empty_value;
+                            #original_field_assigns_by_empty_value
                         }
                     )*
                     #(
                         if let Some(v) = patch.#skip_wrap_field_names {
-                            self.#skip_wrap_field_names = Some(v);
+                            let v = Some(v);
+                            #skip_wrap_field_assigns
                         }
                     )*
                     #(
-                        self.#nesting_field_names.apply(patch.#nesting_field_names);
+                        struct_patch::traits::Patch::apply(&mut self.#nesting_field_names, patch.#nesting_field_names);
                     )*
+                    #after_apply
                 }
 
                 fn into_patch(self) -> #name #generics {
@@ -631,6 +755,288 @@ impl Patch {
             }
         };
 
+        // Hidden setters used by `struct_patch::patch!`, one per named field which is not nested.
+        // The setter takes the value without the `Option` wrapper added by the derive, so the
+        // macro does not need to know how each field is represented in the patch.
+        let literal_setters = fields
+            .iter()
+            .filter(|f| !f.is_nesting())
+            .filter_map(|f| {
+                let ident = f.ident.as_ref()?;
+                let setter = crate::literal::setter_ident(ident);
+                let ty = &f.ty;
+                let value = if f.special_attr.is_empty() {
+                    quote!(Some(v))
+                } else {
+                    quote!(v)
+                };
+                Some(quote! {
+                    #[doc(hidden)]
+                    #[inline]
+                    pub fn #setter(&mut self, v: #ty) {
+                        self.#ident = #value;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let literal_impl = quote! {
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #generics #struct_name #generics #where_clause {
+                #[doc(hidden)]
+                #[inline]
+                pub fn __struct_patch_empty() -> #name #generics {
+                    <Self as struct_patch::traits::Patch< #name #generics >>::new_empty_patch()
+                }
+            }
+
+            #[automatically_derived]
+            impl #generics #name #generics #where_clause {
+                #(#literal_setters)*
+            }
+        };
+
+        #[cfg(feature = "field")]
+        let field_impl = self.field_token_stream();
+        #[cfg(not(feature = "field"))]
+        let field_impl = quote!();
+        #[cfg(feature = "change")]
+        let change_impl = self.change_token_stream();
+        #[cfg(not(feature = "change"))]
+        let change_impl = quote!();
+        let readonly_checks = fields
+            .iter()
+            .filter_map(|f| {
+                let ident = f.ident.as_ref()?;
+                if f.is_nesting() {
+                    let ty = &f.ty;
+                    Some(quote! {
+                        <#ty as struct_patch::traits::Patchable>::__struct_patch_check_readonly(&patch.#ident)?;
+                    })
+                } else if f.readonly {
+                    let ident_str = ident.to_string();
+                    let is_set = f.is_set_token_stream(quote!(patch.#ident));
+                    Some(quote! {
+                        if #is_set {
+                            return Err(struct_patch::error::ReadOnly { field: #ident_str });
+                        }
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        // The readonly fields of the nested structs are checked through the nesting fields, so
+        // the check and the public methods are generated with either of them
+        let (readonly_fn, readonly_impl) = if fields.iter().any(|f| f.readonly || f.is_nesting()) {
+            let readonly_fn = quote! {
+                fn __struct_patch_check_readonly(patch: &#name #generics) -> Result<(), struct_patch::error::ReadOnly> {
+                    #(#readonly_checks)*
+                    Ok(())
+                }
+            };
+            let readonly_impl = quote! {
+                #[automatically_derived]
+                impl #generics #name #generics #where_clause {
+                    /// Returns an error naming the first `#[patch(readonly)]` field set in the patch
+                    pub fn check_readonly(&self) -> Result<(), struct_patch::error::ReadOnly> {
+                        <#struct_name #generics as struct_patch::traits::Patchable>::__struct_patch_check_readonly(self)
+                    }
+                }
+
+                #[automatically_derived]
+                #[allow(dead_code)]
+                impl #generics #struct_name #generics #where_clause {
+                    /// Apply the patch, or return an error without applying it if it sets a
+                    /// `#[patch(readonly)]` field
+                    pub fn try_apply(&mut self, patch: #name #generics) -> Result<(), struct_patch::error::ReadOnly> {
+                        patch.check_readonly()?;
+                        struct_patch::traits::Patch::apply(self, patch);
+                        Ok(())
+                    }
+                }
+            };
+            (readonly_fn, readonly_impl)
+        } else {
+            (quote!(), quote!())
+        };
+
+        // `denied` is the first group of the nesting fields which the caller is not allowed to
+        // change, all the fields nested in such a field are forbidden.
+        let authorize_checks = fields
+            .iter()
+            .filter_map(|f| {
+                let ident = f.ident.as_ref()?;
+                let denied = match &f.group {
+                    Some(group) => quote! {
+                        denied.or(if allowed.contains(&#group) { None } else { Some(#group) })
+                    },
+                    None => quote!(denied),
+                };
+                if f.is_nesting() {
+                    let ty = &f.ty;
+                    return Some(quote! {
+                        <#ty as struct_patch::traits::Patchable>::__struct_patch_authorize(&mut patch.#ident, allowed, #denied, strip)?;
+                    });
+                }
+                let ident_str = ident.to_string();
+                let is_set = f.is_set_token_stream(quote!(patch.#ident));
+                let empty = match f.special_attr.empty_value() {
+                    Some(empty) => quote!(#empty),
+                    None => quote!(None),
+                };
+                Some(quote! {
+                    if let Some(group) = #denied {
+                        if #is_set {
+                            if strip {
+                                patch.#ident = #empty;
+                            } else {
+                                return Err(struct_patch::error::Forbidden { field: #ident_str, group });
+                            }
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        // The groups of the nested structs are checked through the nesting fields, so the check
+        // and the public methods are generated with either of them
+        let (authorize_fn, authorize_impl) = if fields
+            .iter()
+            .any(|f| f.group.is_some() || f.is_nesting())
+        {
+            let authorize_fn = quote! {
+                #[allow(unused_variables)]
+                fn __struct_patch_authorize(
+                    patch: &mut #name #generics,
+                    allowed: &[&str],
+                    denied: Option<&'static str>,
+                    strip: bool,
+                ) -> Result<(), struct_patch::error::Forbidden> {
+                    #(#authorize_checks)*
+                    Ok(())
+                }
+            };
+            let authorize_impl = quote! {
+                #[automatically_derived]
+                impl #generics #name #generics #where_clause {
+                    /// Remove the fields of the `#[patch(group = "...")]` groups which are not
+                    /// allowed from the patch
+                    pub fn strip_unauthorized(&mut self, allowed: &[&str]) {
+                        let _ = <#struct_name #generics as struct_patch::traits::Patchable>::__struct_patch_authorize(self, allowed, None, true);
+                    }
+                }
+
+                #[automatically_derived]
+                #[allow(dead_code)]
+                impl #generics #struct_name #generics #where_clause {
+                    /// Apply the patch, or return an error without applying it if it sets a field
+                    /// of a `#[patch(group = "...")]` group which is not allowed
+                    pub fn apply_authorized(
+                        &mut self,
+                        mut patch: #name #generics,
+                        allowed: &[&str],
+                    ) -> Result<(), struct_patch::error::Forbidden> {
+                        <Self as struct_patch::traits::Patchable>::__struct_patch_authorize(&mut patch, allowed, None, false)?;
+                        struct_patch::traits::Patch::apply(self, patch);
+                        Ok(())
+                    }
+                }
+            };
+            (authorize_fn, authorize_impl)
+        } else {
+            (quote!(), quote!())
+        };
+
+        #[cfg(feature = "field")]
+        let is_changed_fn = self.is_changed_token_stream();
+        #[cfg(not(feature = "field"))]
+        let is_changed_fn = quote!();
+        let has_patch_impl = quote! {
+            #[automatically_derived]
+            impl #generics struct_patch::traits::Patchable for #struct_name #generics #where_clause {
+                type Patch = #name #generics;
+
+                #is_changed_fn
+
+                #readonly_fn
+
+                #authorize_fn
+            }
+        };
+
+        #[cfg(feature = "alloc")]
+        let mask_impl = self.mask_token_stream();
+        #[cfg(not(feature = "alloc"))]
+        let mask_impl = quote!();
+        #[cfg(feature = "alloc")]
+        let pointer_impl = self.pointer_token_stream();
+        #[cfg(not(feature = "alloc"))]
+        let pointer_impl = quote!();
+        #[cfg(feature = "sql")]
+        let sql_impl = if self.sql {
+            self.sql_token_stream()
+        } else {
+            quote!()
+        };
+        #[cfg(not(feature = "sql"))]
+        let sql_impl = quote!();
+        #[cfg(feature = "audit")]
+        let audit_impl = if self.audit {
+            self.audit_token_stream()
+        } else {
+            quote!()
+        };
+        #[cfg(not(feature = "audit"))]
+        let audit_impl = quote!();
+
+        let debug_impl = if redacted {
+            self.debug_token_stream()
+        } else {
+            quote!()
+        };
+        #[cfg(feature = "schemars")]
+        let schema_impl = if schema {
+            let rename_all = crate::schema_rename_all(attributes)?;
+            let mut properties = vec![];
+            for f in fields.iter() {
+                let ident = match f.ident.as_ref() {
+                    Some(ident) => ident,
+                    None => continue,
+                };
+                let name = crate::schema_property_name(ident, &f.attributes, rename_all.as_deref());
+                let name = match name? {
+                    Some(name) => name,
+                    None => continue,
+                };
+                // The schema of the field type without the `Option` wrapper of the patch, so the
+                // property is nullable only if the field is an `Option`
+                let ty = if f.is_nesting() {
+                    f.patch_type()
+                } else {
+                    f.ty.to_token_stream()
+                };
+                properties.push(crate::schema_property(
+                    name,
+                    ty,
+                    f.doc.as_ref(),
+                    f.special_attr.empty_value(),
+                ));
+            }
+            crate::schema_impl(name, generics, properties)
+        } else {
+            quote!()
+        };
+        #[cfg(not(feature = "schemars"))]
+        let schema_impl = quote!();
+        #[cfg(feature = "clap")]
+        let cli_impl = if self.cli {
+            self.cli_token_stream()?
+        } else {
+            quote!()
+        };
+        #[cfg(not(feature = "clap"))]
+        let cli_impl = quote!();
+
         Ok(quote! {
             #patch_struct
 
@@ -640,131 +1046,1377 @@ impl Patch {
 
             #patch_impl
 
+            #has_patch_impl
+
             #op_impl
+
+            #literal_impl
+
+            #readonly_impl
+
+            #authorize_impl
+
+            #mask_impl
+
+            #pointer_impl
+
+            #sql_impl
+
+            #audit_impl
+
+            #debug_impl
+
+            #field_impl
+
+            #change_impl
+
+            #schema_impl
+
+            #cli_impl
         })
     }
 
-    /// Parse the patch struct
-    pub fn from_ast(
-        DeriveInput {
-            ident,
-            data,
+    /// Generate the `Debug` impl of the patch struct, which prints the set redacted fields as
+    /// `Some(<redacted>)`
+    fn debug_token_stream(&self) -> TokenStream {
+        let Patch {
+            patch_struct_name: name,
             generics,
-            attrs,
-            vis,
-        }: syn::DeriveInput,
-    ) -> Result<Patch> {
-        let original_fields = if let syn::Data::Struct(syn::DataStruct { fields, .. }) = data {
-            fields
-        } else {
-            return Err(syn::Error::new(
-                ident.span(),
-                "Patch derive only use for struct",
-            ));
-        };
+            fields,
+            ..
+        } = self;
+        let name_str = name.to_string();
 
-        let mut name = None;
-        let mut attributes = vec![];
-        let mut fields = vec![];
+        let mut debug_generics = generics.clone();
+        let debug_where_clause = debug_generics.make_where_clause();
+        let mut debug_fields = Vec::new();
+        for f in fields {
+            let ident = match &f.ident {
+                Some(ident) => ident,
+                None => continue,
+            };
+            let ident_str = ident.to_string();
+            let value = if !f.redact {
+                let ty = f.patch_type();
+                debug_where_clause
+                    .predicates
+                    .push(syn::parse_quote!(#ty: core::fmt::Debug));
+                quote!(&self.#ident)
+            } else if let Some(empty) = f.special_attr.empty_value() {
+                let is_empty = empty.is_empty_token_stream(quote!(self.#ident));
+                quote! {
+                    if !(#is_empty) {
+                        &struct_patch::__private::Redacted as &dyn core::fmt::Debug
+                    } else {
+                        &self.#ident
+                    }
+                }
+            } else {
+                quote!(&self.#ident.as_ref().map(|_| struct_patch::__private::Redacted))
+            };
+            debug_fields.push(quote!(.field(#ident_str, #value)));
+        }
 
-        for attr in attrs {
-            if attr.path().to_string().as_str() != PATCH {
-                continue;
+        quote! {
+            #[automatically_derived]
+            impl #generics core::fmt::Debug for #name #generics #debug_where_clause {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.debug_struct(#name_str)
+                        #(#debug_fields)*
+                        .finish()
+                }
             }
+        }
+    }
 
-            if let syn::Meta::List(meta) = &attr.meta {
-                if meta.tokens.is_empty() {
-                    continue;
-                }
+    /// Generate the conversions between the patch and the dotted paths of a protobuf `FieldMask`
+    #[cfg(feature = "alloc")]
+    fn mask_token_stream(&self) -> TokenStream {
+        let Patch {
+            struct_name,
+            patch_struct_name: name,
+            generics,
+            fields,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+
+        let mut mask_paths = Vec::new();
+        let mut mask_arms = Vec::new();
+        let mut mask_all = Vec::new();
+        let mut mask_generics = generics.clone();
+        let mask_where_clause = mask_generics.make_where_clause();
+        for f in fields.iter() {
+            let ident = match f.ident.as_ref() {
+                Some(ident) => ident,
+                None => continue,
+            };
+            let ident_str = ident.to_string().trim_start_matches("r#").to_string();
+            if f.is_nesting() {
+                let prefix = format!("{}.", ident_str);
+                mask_paths.push(quote! {
+                    self.#ident.__struct_patch_field_mask(&struct_patch::__private::prefixed(prefix, #prefix), mask);
+                });
+                // A bare path selects all the fields of the nested value, which opts in as well
+                let span = syn::spanned::Spanned::span(&f.ty);
+                mask_arms.push(quote::quote_spanned! {span=>
+                    #ident_str => self.#ident.__struct_patch_mask_path(rest, &mut patch.#ident),
+                });
+                mask_all.push(quote::quote_spanned! {span=>
+                    self.#ident.__struct_patch_mask_path(None, &mut patch.#ident);
+                });
+                continue;
             }
+            // The bound is checked at the field, whose type is then named by the error
+            let ty = &f.struct_ty;
+            mask_where_clause
+                .predicates
+                .push(syn::parse_quote_spanned!(syn::spanned::Spanned::span(ty)=> #ty: Clone));
+            let is_set = f.is_set_token_stream(quote!(self.#ident));
+            mask_paths.push(quote! {
+                if #is_set {
+                    mask.push(struct_patch::__private::prefixed(prefix, #ident_str));
+                }
+            });
+            let value = match (f.retyped, f.special_attr.is_empty()) {
+                (true, true) => {
+                    quote!(Some(struct_patch::traits::Patch::into_patch(self.#ident.clone())))
+                }
+                (true, false) => {
+                    quote!(struct_patch::traits::Patch::into_patch(self.#ident.clone()))
+                }
+                (false, true) => quote!(Some(self.#ident.clone())),
+                (false, false) => quote!(self.#ident.clone()),
+            };
+            mask_arms.push(quote! {
+                #ident_str if rest.is_none() => patch.#ident = #value,
+            });
+            mask_all.push(quote! {
+                patch.#ident = #value;
+            });
+        }
 
-            attr.parse_nested_meta(|meta| {
-                let path = meta.path.to_string();
-                match path.as_str() {
-                    NAME => {
-                        // #[patch(name = "PatchStruct")]
-                        if let Some(lit) = crate::get_lit_str(path, &meta)? {
-                            if name.is_some() {
-                                return Err(meta
-                                    .error("The name attribute can't be defined more than once"));
-                            }
-                            name = Some(lit.parse()?);
+        // The values of the masked fields are cloned into the patch, so `mask_to_patch` is
+        // generated for the structs opting in with `#[patch(mask_to_patch)]`, whose fields are
+        // `Clone`
+        let to_patch_impl = if self.mask_to_patch {
+            quote! {
+                #[automatically_derived]
+                #[allow(dead_code)]
+                impl #generics #struct_name #generics #mask_where_clause {
+                    /// Build a patch setting the fields of the protobuf `FieldMask` paths to a
+                    /// clone of their values in `self`, the paths not matching any field are
+                    /// ignored
+                    pub fn mask_to_patch<S: AsRef<str>>(&self, mask: &[S]) -> #name #generics {
+                        let mut patch = Self::__struct_patch_empty();
+                        for path in mask {
+                            self.__struct_patch_mask_path(Some(path.as_ref()), &mut patch);
                         }
+                        patch
                     }
-                    ATTRIBUTE => {
-                        // #[patch(attribute(derive(Deserialize)))]
-                        // #[patch(attribute(derive(Deserialize, Debug), serde(rename = "foo"))]
-                        let content;
-                        parenthesized!(content in meta.input);
-                        let attribute: TokenStream = content.parse()?;
-                        attributes.push(attribute);
-                    }
-                    _ => {
-                        return Err(meta.error(format_args!(
-                            "unknown patch container attribute `{}`",
-                            path.replace(' ', "")
-                        )));
+
+                    /// Set the fields of the path, or all the fields without a path
+                    #[doc(hidden)]
+                    #[allow(unused_variables)]
+                    pub fn __struct_patch_mask_path(&self, path: Option<&str>, patch: &mut #name #generics) {
+                        let path = match path {
+                            Some(path) => path,
+                            None => {
+                                #(#mask_all)*
+                                return;
+                            }
+                        };
+                        let (head, rest) = match path.split_once('.') {
+                            Some((head, rest)) => (head, Some(rest)),
+                            None => (path, None),
+                        };
+                        match head {
+                            #(#mask_arms)*
+                            _ => {}
+                        }
                     }
                 }
-                Ok(())
-            })?;
-        }
+            }
+        } else {
+            quote!()
+        };
 
-        for field in original_fields {
-            if let Some(f) = Field::from_ast(field)? {
-                fields.push(f);
+        quote! {
+            #[automatically_derived]
+            impl #generics #name #generics #where_clause {
+                /// The paths of the fields set in the patch, the fields of the nesting fields are
+                /// joined with dots as the paths of a protobuf `FieldMask`
+                pub fn field_mask(&self) -> struct_patch::__private::Vec<struct_patch::__private::String> {
+                    let mut mask = struct_patch::__private::Vec::new();
+                    self.__struct_patch_field_mask("", &mut mask);
+                    mask
+                }
+
+                #[doc(hidden)]
+                #[allow(unused_variables, clippy::ptr_arg)]
+                pub fn __struct_patch_field_mask(
+                    &self,
+                    prefix: &str,
+                    mask: &mut struct_patch::__private::Vec<struct_patch::__private::String>,
+                ) {
+                    #(#mask_paths)*
+                }
             }
-        }
 
-        Ok(Patch {
-            visibility: vis,
-            patch_struct_name: name.unwrap_or({
-                let ts = TokenStream::from_str(&format!("{}Patch", &ident,)).unwrap();
-                let lit = LitStr::new(&ts.to_string(), Span::call_site());
-                lit.parse()?
-            }),
-            struct_name: ident,
-            generics,
-            attributes,
-            fields,
-        })
+            #to_patch_impl
+        }
     }
-}
 
-impl Field {
-    /// Generate the token stream for the Patch struct fields
-    pub fn to_token_stream(&self) -> Result<TokenStream> {
-        let Field {
-            ident,
-            ty,
-            attributes,
-            #[cfg(feature = "nesting")]
-            nesting,
-            special_attr,
+    /// Generate the `Patch` impls of `Box`, `Rc`, `Arc` and `Cow` of the struct, which patch the
+    /// pointed struct in place, the shared `Rc` and `Arc` and the borrowed `Cow` are cloned first
+    #[cfg(feature = "alloc")]
+    fn pointer_token_stream(&self) -> TokenStream {
+        let Patch {
+            struct_name,
+            patch_struct_name: name,
+            generics,
             ..
         } = self;
+        let where_clause = &generics.where_clause;
+        let patch_trait = quote!(struct_patch::traits::Patch<#name #generics>);
+        let struct_original = quote!(<#struct_name #generics as #patch_trait>);
 
-        let attributes = attributes
-            .iter()
-            .map(|a| {
-                quote! {
-                    #[#a]
-                }
-            })
-            .collect::<Vec<_>>();
-        match ident {
-            #[cfg(not(feature = "nesting"))]
-            Some(ident) => {
-                if !special_attr.is_empty() {
-                    Ok(quote! {
-                        #(#attributes)*
-                        pub #ident: #ty,
-                    })
-                } else {
-                    Ok(quote! {
-                        #(#attributes)*
-                        pub #ident: Option<#ty>,
-                    })
+        // The shared and borrowed structs are cloned when they are patched, so their impls are
+        // generic over the pointed type to bound it on `Clone`.  `Box` is fundamental, the orphan
+        // rule only allows its impl for the struct itself.  As the `Rc` and `Arc` impls match any
+        // pointed type, `apply` called with the method syntax on an `Arc<Wrapper>` resolves to
+        // them before the inherent `apply` of `Wrapper`, so the wrappers of the lib, as `Shared`,
+        // name their methods otherwise.
+        let original = quote!(<__T as #patch_trait>);
+        let mut clone_generics = generics.clone();
+        let lifetimes = clone_generics.lifetimes().count();
+        clone_generics
+            .params
+            .insert(lifetimes, syn::parse_quote!(__T));
+        clone_generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote!(__T: #patch_trait + Clone));
+        let clone_where_clause = &clone_generics.where_clause;
+        let mut cow_generics = clone_generics.clone();
+        cow_generics
+            .params
+            .insert(0, syn::parse_quote!('__struct_patch_cow));
+
+        let shared_impls = [
+            (quote!(struct_patch::__private::Rc), quote!()),
+            (
+                quote!(struct_patch::__private::Arc),
+                quote!(#[cfg(target_has_atomic = "ptr")]),
+            ),
+        ]
+        .iter()
+        .map(|(pointer, cfg)| {
+            quote! {
+                #cfg
+                #[automatically_derived]
+                impl #clone_generics #patch_trait for #pointer<__T> #clone_where_clause {
+                    fn apply(&mut self, patch: #name #generics) {
+                        #original::apply(#pointer::make_mut(self), patch);
+                    }
+
+                    fn into_patch(self) -> #name #generics {
+                        let value = #pointer::try_unwrap(self).unwrap_or_else(|v| (*v).clone());
+                        #original::into_patch(value)
+                    }
+
+                    fn into_patch_by_diff(self, previous_struct: Self) -> #name #generics {
+                        let value = #pointer::try_unwrap(self).unwrap_or_else(|v| (*v).clone());
+                        let previous_struct = #pointer::try_unwrap(previous_struct)
+                            .unwrap_or_else(|v| (*v).clone());
+                        #original::into_patch_by_diff(value, previous_struct)
+                    }
+
+                    fn new_empty_patch() -> #name #generics {
+                        #original::new_empty_patch()
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+        quote! {
+            #[automatically_derived]
+            impl #generics #patch_trait for struct_patch::__private::Box<#struct_name #generics> #where_clause {
+                fn apply(&mut self, patch: #name #generics) {
+                    #struct_original::apply(&mut **self, patch);
+                }
+
+                fn into_patch(self) -> #name #generics {
+                    #struct_original::into_patch(*self)
+                }
+
+                fn into_patch_by_diff(self, previous_struct: Self) -> #name #generics {
+                    #struct_original::into_patch_by_diff(*self, *previous_struct)
+                }
+
+                fn new_empty_patch() -> #name #generics {
+                    #struct_original::new_empty_patch()
+                }
+            }
+
+            #(#shared_impls)*
+
+            #[automatically_derived]
+            impl #cow_generics #patch_trait
+                for struct_patch::__private::Cow<'__struct_patch_cow, __T>
+                #clone_where_clause
+            {
+                fn apply(&mut self, patch: #name #generics) {
+                    #original::apply(self.to_mut(), patch);
+                }
+
+                fn into_patch(self) -> #name #generics {
+                    #original::into_patch(self.into_owned())
+                }
+
+                fn into_patch_by_diff(self, previous_struct: Self) -> #name #generics {
+                    #original::into_patch_by_diff(self.into_owned(), previous_struct.into_owned())
+                }
+
+                fn new_empty_patch() -> #name #generics {
+                    #original::new_empty_patch()
+                }
+            }
+        }
+    }
+
+    /// Generate the `ToSqlSet` impl.  The bounds are not higher-ranked, so the fields without a SQL
+    /// value are reported at the struct opting in with `#[patch(sql)]`.
+    #[cfg(feature = "sql")]
+    fn sql_token_stream(&self) -> TokenStream {
+        let Patch {
+            patch_struct_name: name,
+            generics,
+            fields,
+            ..
+        } = self;
+
+        let mut sql_generics = generics.clone();
+        let where_clause = sql_generics.make_where_clause();
+        let mut assignments = Vec::new();
+        for f in fields.iter() {
+            let ident = match f.ident.as_ref() {
+                Some(ident) => ident,
+                None => continue,
+            };
+            let column = match &f.column {
+                Some(column) => column.value(),
+                None => ident.to_string().trim_start_matches("r#").to_string(),
+            };
+            if f.is_nesting() {
+                let patch_type = f.patch_type();
+                where_clause
+                    .predicates
+                    .push(syn::parse_quote!(#patch_type: struct_patch::sql::ToSqlSet));
+                let prefix = format!("{}_", column);
+                assignments.push(quote! {
+                    struct_patch::sql::ToSqlSet::push_sql_set(
+                        &self.#ident,
+                        &struct_patch::__private::prefixed(prefix, #prefix),
+                        set,
+                    );
+                });
+                continue;
+            }
+            let ty = &f.ty;
+            where_clause
+                .predicates
+                .push(syn::parse_quote!(#ty: struct_patch::sql::ToSqlValue));
+            let assignment = if f.special_attr.is_empty() {
+                quote! {
+                    if let Some(v) = &self.#ident {
+                        set.push(prefix, #column, struct_patch::sql::ToSqlValue::to_sql_value(v));
+                    }
+                }
+            } else {
+                let is_set = f.is_set_token_stream(quote!(self.#ident));
+                quote! {
+                    if #is_set {
+                        set.push(prefix, #column, struct_patch::sql::ToSqlValue::to_sql_value(&self.#ident));
+                    }
+                }
+            };
+            assignments.push(assignment);
+        }
+
+        quote! {
+            #[automatically_derived]
+            impl #generics struct_patch::sql::ToSqlSet for #name #generics #where_clause {
+                #[allow(unused_variables)]
+                fn push_sql_set(&self, prefix: &str, set: &mut struct_patch::sql::SetClause) {
+                    #(#assignments)*
+                }
+            }
+        }
+    }
+
+    /// Generate the `Audited` impl reading and writing the fields by their dotted paths, and
+    /// `apply_audited`.  The renamed fields are not audited, their types in the struct are unknown.
+    /// The bounds are not higher-ranked, so the fields which can not be (de)serialized are reported
+    /// at the struct opting in with `#[patch(audit)]`.
+    #[cfg(feature = "audit")]
+    fn audit_token_stream(&self) -> TokenStream {
+        let Patch {
+            struct_name,
+            patch_struct_name: name,
+            generics,
+            fields,
+            ..
+        } = self;
+
+        let mut audit_generics = generics.clone();
+        let audit_where_clause = audit_generics.make_where_clause();
+        let mut value_arms = Vec::new();
+        let mut restore_arms = Vec::new();
+        for f in fields.iter() {
+            let ident = match f.ident.as_ref() {
+                Some(ident) => ident,
+                None => continue,
+            };
+            let ident_str = ident.to_string().trim_start_matches("r#").to_string();
+            let ty = &f.ty;
+            if f.is_nesting() {
+                audit_where_clause.predicates.push(syn::parse_quote!(
+                    #ty: struct_patch::audit::Audited
+                ));
+                value_arms.push(quote! {
+                    #ident_str => struct_patch::audit::Audited::audit_value(&self.#ident, rest?),
+                });
+                restore_arms.push(quote! {
+                    #ident_str => struct_patch::audit::Audited::audit_restore(&mut self.#ident, rest?, value),
+                });
+                continue;
+            }
+            // The renamed fields are recorded as a whole, as the other fields
+            let ty = &f.struct_ty;
+            audit_where_clause.predicates.push(syn::parse_quote!(
+                #ty: struct_patch::__private::serde::Serialize
+                    + struct_patch::__private::serde::de::DeserializeOwned
+            ));
+            let redact = f.redact;
+            value_arms.push(quote! {
+                #ident_str if rest.is_none() => Some((
+                    struct_patch::__private::serde_json::to_value(&self.#ident).ok()?,
+                    #redact,
+                )),
+            });
+            restore_arms.push(quote! {
+                #ident_str if rest.is_none() => Some(
+                    struct_patch::__private::serde_json::from_value(value).map(|v| self.#ident = v),
+                ),
+            });
+        }
+        let split_path = quote! {
+            let (head, rest) = match path.split_once('.') {
+                Some((head, rest)) => (head, Some(rest)),
+                None => (path, None),
+            };
+        };
+
+        quote! {
+            #[automatically_derived]
+            impl #generics struct_patch::audit::Audited for #struct_name #generics #audit_where_clause {
+                #[allow(unused_variables)]
+                fn audit_value(
+                    &self,
+                    path: &str,
+                ) -> Option<(struct_patch::__private::serde_json::Value, bool)> {
+                    #split_path
+                    match head {
+                        #(#value_arms)*
+                        _ => None,
+                    }
+                }
+
+                #[allow(unused_variables)]
+                fn audit_restore(
+                    &mut self,
+                    path: &str,
+                    value: struct_patch::__private::serde_json::Value,
+                ) -> Option<struct_patch::__private::serde_json::Result<()>> {
+                    #split_path
+                    match head {
+                        #(#restore_arms)*
+                        _ => None,
+                    }
+                }
+            }
+
+            #[automatically_derived]
+            #[allow(dead_code)]
+            impl #generics #struct_name #generics #audit_where_clause {
+                /// Apply the patch, and return the records of the fields changed by it
+                pub fn apply_audited(
+                    &mut self,
+                    patch: #name #generics,
+                ) -> struct_patch::__private::Vec<struct_patch::audit::AuditRecord> {
+                    let paths = patch.field_mask();
+                    struct_patch::audit::apply_audited(self, patch, paths)
+                }
+            }
+        }
+    }
+
+    /// Generate the field enum and the helpers to select the fields of the patch struct
+    #[cfg(feature = "field")]
+    fn field_token_stream(&self) -> TokenStream {
+        let Patch {
+            visibility,
+            struct_name,
+            patch_struct_name: name,
+            generics,
+            field_enum_name,
+            fields,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+
+        let named_fields = fields
+            .iter()
+            .filter(|f| f.ident.is_some())
+            .collect::<Vec<_>>();
+        let field_names = named_fields
+            .iter()
+            .map(|f| f.ident.as_ref())
+            .collect::<Vec<_>>();
+        let field_name_strs = named_fields
+            .iter()
+            .map(|f| f.ident.as_ref().map(|i| i.to_string()))
+            .collect::<Vec<_>>();
+        let variants = named_fields
+            .iter()
+            .map(|f| f.variant_ident())
+            .collect::<Vec<_>>();
+        let is_set = named_fields
+            .iter()
+            .map(|f| {
+                let ident = &f.ident;
+                f.is_set_token_stream(quote!(self.#ident))
+            })
+            .collect::<Vec<_>>();
+
+        #[cfg(feature = "alloc")]
+        let split_fn = quote! {
+            /// Split the patch into patches with only one field set each
+            pub fn split(mut self) -> struct_patch::__private::Vec<Self> {
+                let mut patches = struct_patch::__private::Vec::new();
+                #(
+                    if #is_set {
+                        let mut patch = <#struct_name #generics as struct_patch::traits::Patch< #name #generics >>::new_empty_patch();
+                        core::mem::swap(&mut patch.#field_names, &mut self.#field_names);
+                        patches.push(patch);
+                    }
+                )*
+                patches
+            }
+        };
+        #[cfg(not(feature = "alloc"))]
+        let split_fn = quote!();
+
+        quote! {
+            /// The fields of the patch
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+            #[allow(clippy::enum_variant_names)]
+            #visibility enum #field_enum_name {
+                #(#variants,)*
+            }
+
+            #[automatically_derived]
+            impl #field_enum_name {
+                /// All the fields in declaration order
+                pub const ALL: &'static [#field_enum_name] = &[#(#field_enum_name::#variants,)*];
+
+                /// The name of the field
+                pub fn name(&self) -> &'static str {
+                    match *self {
+                        #(#field_enum_name::#variants => #field_name_strs,)*
+                    }
+                }
+            }
+
+            #[automatically_derived]
+            impl #generics struct_patch::traits::Fields for #name #generics #where_clause {
+                type Field = #field_enum_name;
+
+                const FIELDS: &'static [#field_enum_name] = #field_enum_name::ALL;
+
+                fn is_set(&self, field: #field_enum_name) -> bool {
+                    #name::is_set(self, field)
+                }
+            }
+
+            #[automatically_derived]
+            impl #generics #name #generics #where_clause {
+                /// Returns `true` if the field is set in the patch
+                pub fn is_set(&self, field: #field_enum_name) -> bool {
+                    match field {
+                        #(#field_enum_name::#variants => #is_set,)*
+                    }
+                }
+
+                /// Iterate the fields set in the patch
+                pub fn fields(&self) -> impl Iterator<Item = #field_enum_name> + '_ {
+                    #field_enum_name::ALL
+                        .iter()
+                        .copied()
+                        .filter(move |f| self.is_set(*f))
+                }
+
+                /// Reset the field to empty
+                pub fn clear(&mut self, field: #field_enum_name) {
+                    match field {
+                        #(
+                            #field_enum_name::#variants => {
+                                self.#field_names = <#struct_name #generics as struct_patch::traits::Patch< #name #generics >>::new_empty_patch().#field_names;
+                            }
+                        )*
+                    }
+                }
+
+                /// Keep only the fields for which the predicate returns `true`
+                pub fn retain<F: FnMut(#field_enum_name) -> bool>(&mut self, mut f: F) {
+                    for field in #field_enum_name::ALL {
+                        if !f(*field) {
+                            self.clear(*field);
+                        }
+                    }
+                }
+
+                /// Keep only the given fields
+                pub fn only(&mut self, fields: &[#field_enum_name]) {
+                    self.retain(|f| fields.contains(&f));
+                }
+
+                #split_fn
+            }
+        }
+    }
+
+    /// Generate `Patchable::is_changed_by`, comparing the fields set in the patch with the values
+    /// of the struct
+    #[cfg(feature = "field")]
+    fn is_changed_token_stream(&self) -> TokenStream {
+        let Patch {
+            patch_struct_name: name,
+            generics,
+            field_enum_name,
+            fields,
+            ..
+        } = self;
+
+        let (variants, is_changed): (Vec<_>, Vec<_>) = fields
+            .iter()
+            .filter(|f| f.ident.is_some())
+            .map(|f| (f.variant_ident(), f.is_changed_token_stream()))
+            .unzip();
+
+        quote! {
+            #[allow(unused_variables)]
+            fn is_changed_by(
+                &self,
+                patch: &#name #generics,
+                field: <#name #generics as struct_patch::traits::Fields>::Field,
+            ) -> bool {
+                match field {
+                    #(#field_enum_name::#variants => #is_changed,)*
+                }
+            }
+        }
+    }
+
+    /// Generate the change enum and the helpers to turn the patch into typed change events
+    #[cfg(feature = "change")]
+    fn change_token_stream(&self) -> TokenStream {
+        let Patch {
+            visibility,
+            struct_name,
+            patch_struct_name: name,
+            generics,
+            field_enum_name,
+            change_enum_name,
+            fields,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+
+        let named_fields = fields
+            .iter()
+            .filter(|f| f.ident.is_some())
+            .collect::<Vec<_>>();
+        let variants = named_fields
+            .iter()
+            .map(|f| f.variant_ident())
+            .collect::<Vec<_>>();
+        let payload_types = named_fields
+            .iter()
+            .map(|f| f.change_payload_type())
+            .collect::<Vec<_>>();
+        let changes = named_fields
+            .iter()
+            .map(|f| {
+                let ident = &f.ident;
+                let variant = f.variant_ident();
+                if f.is_nesting() {
+                    quote!(self.#ident.into_changes().map(#change_enum_name::#variant))
+                } else if let Some(empty) = f.special_attr.empty_value() {
+                    let is_empty = empty.is_empty_token_stream(quote!(self.#ident));
+                    quote! {
+                        if !(#is_empty) {
+                            Some(#change_enum_name::#variant(self.#ident))
+                        } else {
+                            None
+                        }
+                        .into_iter()
+                    }
+                } else if matches!(f.special_attr, SpecialAttr::SkipWrap) {
+                    quote!(self.#ident.map(|v| #change_enum_name::#variant(Some(v))).into_iter())
+                } else {
+                    quote!(self.#ident.map(#change_enum_name::#variant).into_iter())
+                }
+            })
+            .collect::<Vec<_>>();
+        let apply_changes = named_fields
+            .iter()
+            .map(|f| {
+                let ident = &f.ident;
+                if f.is_nesting() {
+                    quote!(self.#ident.__struct_patch_apply_change(v))
+                } else if f.retyped {
+                    quote!(struct_patch::traits::Patch::apply(&mut self.#ident, v))
+                } else {
+                    let assign = f.assign_token_stream();
+                    quote!({ #assign })
+                }
+            })
+            .collect::<Vec<_>>();
+        let nesting_field_names = named_fields
+            .iter()
+            .filter(|f| f.is_nesting())
+            .map(|f| &f.ident)
+            .collect::<Vec<_>>();
+        let after_apply = self.after_apply.as_ref().map(|f| quote!(#f(self);));
+
+        // `Clone` and `Debug` follow the derives requested for the patch struct
+        let clone_impl = if self
+            .attributes
+            .iter()
+            .any(|a| crate::has_derive(a, "Clone"))
+        {
+            let mut clone_generics = generics.clone();
+            let clone_where_clause = clone_generics.make_where_clause();
+            for ty in payload_types.iter() {
+                clone_where_clause
+                    .predicates
+                    .push(syn::parse_quote!(#ty: Clone));
+            }
+            quote! {
+                #[automatically_derived]
+                impl #generics Clone for #change_enum_name #generics #clone_where_clause {
+                    fn clone(&self) -> Self {
+                        match *self {
+                            #(#change_enum_name::#variants(ref v) => #change_enum_name::#variants(v.clone()),)*
+                        }
+                    }
+                }
+            }
+        } else {
+            quote!()
+        };
+        let debug_impl = if self
+            .attributes
+            .iter()
+            .any(|a| crate::has_derive(a, "Debug"))
+        {
+            let mut debug_generics = generics.clone();
+            let debug_where_clause = debug_generics.make_where_clause();
+            let mut debug_arms = Vec::new();
+            for (f, (variant, ty)) in named_fields
+                .iter()
+                .zip(variants.iter().zip(payload_types.iter()))
+            {
+                let variant_str = variant.to_string();
+                if f.redact {
+                    debug_arms.push(quote! {
+                        #change_enum_name::#variant(_) => f
+                            .debug_tuple(#variant_str)
+                            .field(&struct_patch::__private::Redacted)
+                            .finish(),
+                    });
+                } else {
+                    debug_where_clause
+                        .predicates
+                        .push(syn::parse_quote!(#ty: core::fmt::Debug));
+                    debug_arms.push(quote! {
+                        #change_enum_name::#variant(ref v) => f.debug_tuple(#variant_str).field(v).finish(),
+                    });
+                }
+            }
+            quote! {
+                #[automatically_derived]
+                impl #generics core::fmt::Debug for #change_enum_name #generics #debug_where_clause {
+                    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                        match *self {
+                            #(#debug_arms)*
+                        }
+                    }
+                }
+            }
+        } else {
+            quote!()
+        };
+
+        quote! {
+            /// A change of a field, carrying the new value
+            #[allow(clippy::enum_variant_names, dead_code)]
+            #visibility enum #change_enum_name #generics #where_clause {
+                #(#variants(#payload_types),)*
+            }
+
+            #clone_impl
+
+            #debug_impl
+
+            #[automatically_derived]
+            impl #generics #change_enum_name #generics #where_clause {
+                /// The field changed
+                pub fn field(&self) -> #field_enum_name {
+                    match *self {
+                        #(#change_enum_name::#variants(_) => #field_enum_name::#variants,)*
+                    }
+                }
+            }
+
+            #[automatically_derived]
+            impl #generics #name #generics #where_clause {
+                /// Turn the patch into the changes of the fields set in it
+                pub fn into_changes(self) -> impl Iterator<Item = #change_enum_name #generics> {
+                    core::iter::empty::<#change_enum_name #generics>()
+                        #(.chain(#changes))*
+                }
+            }
+
+            #[automatically_derived]
+            impl #generics #struct_name #generics #where_clause {
+                /// Apply a single change
+                pub fn apply_change(&mut self, change: #change_enum_name #generics) {
+                    self.__struct_patch_apply_change(change);
+                    self.__struct_patch_after_apply();
+                }
+
+                /// Apply a patch, and pass each change to the sink before it is applied, the
+                /// `after_apply` hooks run once all the changes are applied, as with `apply`
+                pub fn apply_with_events(
+                    &mut self,
+                    patch: #name #generics,
+                    mut sink: impl FnMut(&#change_enum_name #generics),
+                ) {
+                    for change in patch.into_changes() {
+                        sink(&change);
+                        self.__struct_patch_apply_change(change);
+                    }
+                    self.__struct_patch_after_apply();
+                }
+
+                /// Apply a single change without the `after_apply` hooks
+                #[doc(hidden)]
+                #[allow(unreachable_code)]
+                pub fn __struct_patch_apply_change(&mut self, change: #change_enum_name #generics) {
+                    match change {
+                        #(#change_enum_name::#variants(v) => #apply_changes,)*
+                    }
+                }
+
+                /// Run the `after_apply` hooks of the nesting fields and of the struct
+                #[doc(hidden)]
+                pub fn __struct_patch_after_apply(&mut self) {
+                    #(self.#nesting_field_names.__struct_patch_after_apply();)*
+                    #after_apply
+                }
+            }
+        }
+    }
+
+    /// Generate the `clap::Args` implementation of the patch struct, each field becomes a
+    /// `--kebab-name` option, and the options of nested fields are prefixed with the field name
+    #[cfg(feature = "clap")]
+    fn cli_token_stream(&self) -> Result<TokenStream> {
+        let Patch {
+            struct_name,
+            patch_struct_name: name,
+            generics,
+            fields,
+            ..
+        } = self;
+        let where_clause = &generics.where_clause;
+        let clap = quote!(struct_patch::__private::clap);
+
+        let mut args = Vec::new();
+        let mut updates = Vec::new();
+        for f in fields {
+            let ident = f.ident.as_ref().ok_or_else(|| {
+                syn::Error::new(
+                    struct_name.span(),
+                    "`cli` only works on the struct with named fields",
+                )
+            })?;
+            if f.readonly {
+                continue;
+            }
+            let long = crate::kebab_name(ident);
+            let ty = &f.ty;
+
+            if f.is_nesting() {
+                let patch_type = Ident::new(
+                    &format!("{}Patch", &ty.to_token_stream()),
+                    Span::call_site(),
+                );
+                let prefix = format!("{}-", long);
+                args.push(quote! {
+                    let cmd = #patch_type::__struct_patch_augment_args(cmd, &struct_patch::__private::prefixed(prefix, #prefix));
+                });
+                updates.push(quote! {
+                    self.#ident.__struct_patch_update_from_arg_matches(matches, &struct_patch::__private::prefixed(prefix, #prefix))?;
+                });
+                continue;
+            }
+            if f.retyped {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "`cli` can not make a command line option for the renamed field, please use `nesting`",
+                ));
+            }
+
+            let value_name = long.to_uppercase().replace('-', "_");
+            let help = f.doc.as_ref().map(|h| quote!(.help(#h)));
+            let (action, update) = if is_bool(ty) {
+                let value = match &f.special_attr {
+                    SpecialAttr::EmptyValue(empty) => quote!(!(#empty)),
+                    _ => quote!(Some(true)),
+                };
+                (
+                    quote!(.action(#clap::ArgAction::SetTrue)),
+                    quote! {
+                        if matches.get_flag(&id) {
+                            self.#ident = #value;
+                        }
+                    },
+                )
+            } else {
+                let (value_ty, value) = match (&f.special_attr, crate::option_inner(ty)) {
+                    (SpecialAttr::SkipWrap, Some(inner)) => (inner, quote!(Some(v.clone()))),
+                    (SpecialAttr::SkipWrap, None) => {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "`cli` needs the `skip_wrap` field to be `Option<...>`",
+                        ));
+                    }
+                    (SpecialAttr::EmptyValue(_), _) => (ty, quote!(v.clone())),
+                    (SpecialAttr::None, Some(inner)) => (inner, quote!(Some(Some(v.clone())))),
+                    (SpecialAttr::None, None) => (ty, quote!(Some(v.clone()))),
+                };
+                (
+                    quote! {
+                        .action(#clap::ArgAction::Set)
+                        .value_name(#value_name)
+                        .value_parser(#clap::value_parser!(#value_ty))
+                    },
+                    quote! {
+                        if let Some(v) = matches.get_one::<#value_ty>(&id) {
+                            self.#ident = #value;
+                        }
+                    },
+                )
+            };
+            args.push(quote! {
+                let cmd = {
+                    let id = struct_patch::__private::prefixed(prefix, #long);
+                    cmd.arg(#clap::Arg::new(id.clone()).long(id) #action #help)
+                };
+            });
+            updates.push(quote! {
+                {
+                    let id = struct_patch::__private::prefixed(prefix, #long);
+                    #update
+                }
+            });
+        }
+
+        Ok(quote! {
+            #[automatically_derived]
+            #[allow(unused_variables)]
+            impl #generics #name #generics #where_clause {
+                #[doc(hidden)]
+                pub fn __struct_patch_augment_args(cmd: #clap::Command, prefix: &str) -> #clap::Command {
+                    #(#args)*
+                    cmd
+                }
+
+                #[doc(hidden)]
+                pub fn __struct_patch_update_from_arg_matches(
+                    &mut self,
+                    matches: &#clap::ArgMatches,
+                    prefix: &str,
+                ) -> Result<(), #clap::Error> {
+                    #(#updates)*
+                    Ok(())
+                }
+            }
+
+            #[automatically_derived]
+            impl #generics #clap::Args for #name #generics #where_clause {
+                fn augment_args(cmd: #clap::Command) -> #clap::Command {
+                    Self::__struct_patch_augment_args(cmd, "")
+                }
+
+                fn augment_args_for_update(cmd: #clap::Command) -> #clap::Command {
+                    Self::__struct_patch_augment_args(cmd, "")
+                }
+            }
+
+            #[automatically_derived]
+            impl #generics #clap::FromArgMatches for #name #generics #where_clause {
+                fn from_arg_matches(matches: &#clap::ArgMatches) -> Result<Self, #clap::Error> {
+                    let mut patch = <#struct_name #generics>::__struct_patch_empty();
+                    patch.__struct_patch_update_from_arg_matches(matches, "")?;
+                    Ok(patch)
+                }
+
+                fn update_from_arg_matches(&mut self, matches: &#clap::ArgMatches) -> Result<(), #clap::Error> {
+                    self.__struct_patch_update_from_arg_matches(matches, "")
+                }
+            }
+        })
+    }
+
+    /// The name of the patch struct
+    pub(crate) fn patch_struct_name(&self) -> &Ident {
+        &self.patch_struct_name
+    }
+
+    /// The named fields of the patch struct with their shapes, used by the filler of the same
+    /// struct to convert from and into the patch, the renamed fields without nesting are left out
+    pub(crate) fn shaped_fields(&self) -> Vec<(&Ident, PatchShape)> {
+        self.fields
+            .iter()
+            .filter_map(|f| {
+                let ident = f.ident.as_ref()?;
+                let shape = if f.is_nesting() {
+                    PatchShape::Nesting
+                } else if f.retyped {
+                    return None;
+                } else if f.special_attr.is_empty() {
+                    PatchShape::Wrapped
+                } else {
+                    PatchShape::Unwrapped(f.is_set_token_stream(quote!(patch.#ident)))
+                };
+                Some((ident, shape))
+            })
+            .collect()
+    }
+
+    /// Parse the patch struct
+    pub fn from_ast(
+        DeriveInput {
+            ident,
+            data,
+            generics,
+            attrs,
+            vis,
+        }: syn::DeriveInput,
+    ) -> Result<Patch> {
+        let original_fields = if let syn::Data::Struct(syn::DataStruct { fields, .. }) = data {
+            fields
+        } else {
+            return Err(syn::Error::new(
+                ident.span(),
+                "Patch derive only use for struct",
+            ));
+        };
+
+        let mut name = None;
+        let mut attributes = vec![];
+        let mut fields = vec![];
+        let mut after_apply = None;
+        #[cfg(feature = "clap")]
+        let mut cli = false;
+        #[cfg(feature = "audit")]
+        let mut audit = false;
+        #[cfg(feature = "sql")]
+        let mut sql = false;
+        #[cfg(feature = "alloc")]
+        let mut mask_to_patch = false;
+
+        for attr in attrs {
+            if attr.path().to_string().as_str() != PATCH {
+                continue;
+            }
+
+            if let syn::Meta::List(meta) = &attr.meta {
+                if meta.tokens.is_empty() {
+                    continue;
+                }
+            }
+
+            attr.parse_nested_meta(|meta| {
+                let path = meta.path.to_string();
+                match path.as_str() {
+                    NAME => {
+                        // #[patch(name = "PatchStruct")]
+                        if let Some(lit) = crate::get_lit_str(path, &meta)? {
+                            if name.is_some() {
+                                return Err(meta
+                                    .error("The name attribute can't be defined more than once"));
+                            }
+                            name = Some(lit.parse()?);
+                        }
+                    }
+                    ATTRIBUTE => {
+                        // #[patch(attribute(derive(Deserialize)))]
+                        // #[patch(attribute(derive(Deserialize, Debug), serde(rename = "foo"))]
+                        let content;
+                        parenthesized!(content in meta.input);
+                        let attribute: TokenStream = content.parse()?;
+                        attributes.push(attribute);
+                    }
+                    AFTER_APPLY => {
+                        // #[patch(after_apply = path::fn)]
+                        if after_apply.is_some() {
+                            return Err(meta
+                                .error("The after_apply attribute can't be defined more than once"));
+                        }
+                        after_apply = Some(meta.value()?.parse()?);
+                    }
+                    #[cfg(feature = "clap")]
+                    CLI => {
+                        // #[patch(cli)]
+                        cli = true;
+                    }
+                    #[cfg(not(feature = "clap"))]
+                    CLI => {
+                        return Err(meta.error("#[patch(cli)] only work with `clap` feature"));
+                    }
+                    #[cfg(feature = "audit")]
+                    AUDIT => {
+                        // #[patch(audit)]
+                        audit = true;
+                    }
+                    #[cfg(not(feature = "audit"))]
+                    AUDIT => {
+                        return Err(meta.error("#[patch(audit)] only work with `audit` feature"));
+                    }
+                    #[cfg(feature = "sql")]
+                    SQL => {
+                        // #[patch(sql)]
+                        sql = true;
+                    }
+                    #[cfg(not(feature = "sql"))]
+                    SQL => {
+                        return Err(meta.error("#[patch(sql)] only work with `sql` feature"));
+                    }
+                    #[cfg(feature = "alloc")]
+                    MASK_TO_PATCH => {
+                        // #[patch(mask_to_patch)]
+                        mask_to_patch = true;
+                    }
+                    #[cfg(not(feature = "alloc"))]
+                    MASK_TO_PATCH => {
+                        return Err(
+                            meta.error("#[patch(mask_to_patch)] only work with `alloc` feature")
+                        );
+                    }
+                    _ => {
+                        return Err(meta.error(format_args!(
+                            "unknown patch container attribute `{}`",
+                            path.replace(' ', "")
+                        )));
+                    }
+                }
+                Ok(())
+            })?;
+        }
+
+        for field in original_fields {
+            if let Some(f) = Field::from_ast(field)? {
+                fields.push(f);
+            }
+        }
+
+        Ok(Patch {
+            visibility: vis,
+            patch_struct_name: name.unwrap_or({
+                let ts = TokenStream::from_str(&format!("{}Patch", &ident,)).unwrap();
+                let lit = LitStr::new(&ts.to_string(), Span::call_site());
+                lit.parse()?
+            }),
+            #[cfg(feature = "field")]
+            field_enum_name: Ident::new(&format!("{}Field", &ident), ident.span()),
+            #[cfg(feature = "change")]
+            change_enum_name: Ident::new(&format!("{}Change", &ident), ident.span()),
+            struct_name: ident,
+            generics,
+            attributes,
+            fields,
+            after_apply,
+            #[cfg(feature = "clap")]
+            cli,
+            #[cfg(feature = "audit")]
+            audit,
+            #[cfg(feature = "sql")]
+            sql,
+            #[cfg(feature = "alloc")]
+            mask_to_patch,
+        })
+    }
+}
+
+impl Field {
+    /// The variant of the field in the field enum
+    #[cfg(feature = "field")]
+    fn variant_ident(&self) -> Ident {
+        crate::variant_ident(self.ident.as_ref().expect("only named fields have variants"))
+    }
+
+    /// Generate the expression checking the field of the patch is set
+    fn is_set_token_stream(&self, value: TokenStream) -> TokenStream {
+        if self.is_nesting() {
+            quote!(!struct_patch::traits::Status::is_empty(&#value))
+        } else if let Some(empty) = self.special_attr.empty_value() {
+            let is_empty = empty.is_empty_token_stream(value);
+            quote!(!(#is_empty))
+        } else {
+            quote!(#value.is_some())
+        }
+    }
+
+    /// Generate the expression checking the field of `patch` is set to a value other than the one
+    /// of `self`, the fields patched by their own patch type are changed when they are set
+    #[cfg(feature = "field")]
+    fn is_changed_token_stream(&self) -> TokenStream {
+        let ident = &self.ident;
+        if self.readonly {
+            quote!(false)
+        } else if self.is_nesting() {
+            let patch_type = self.patch_type();
+            quote! {
+                <#patch_type as struct_patch::traits::Fields>::FIELDS
+                    .iter()
+                    .any(|f| struct_patch::traits::Patchable::is_changed_by(&self.#ident, &patch.#ident, *f))
+            }
+        } else if self.retyped {
+            self.is_set_token_stream(quote!(patch.#ident))
+        } else if let Some(empty) = self.special_attr.empty_value() {
+            let is_empty = empty.is_empty_token_stream(quote!(patch.#ident));
+            quote!(!(#is_empty) && self.#ident != patch.#ident)
+        } else if matches!(self.special_attr, SpecialAttr::SkipWrap) {
+            quote!(patch.#ident.is_some() && self.#ident != patch.#ident)
+        } else {
+            quote!(patch.#ident.as_ref().map_or(false, |v| self.#ident != *v))
+        }
+    }
+
+    /// The type carried by the variant of the field in the change enum
+    #[cfg(feature = "change")]
+    fn change_payload_type(&self) -> TokenStream {
+        let ty = &self.ty;
+        if self.is_nesting() {
+            let change_type = Ident::new(
+                &format!("{}Change", &ty.to_token_stream()),
+                Span::call_site(),
+            );
+            quote!(#change_type)
+        } else {
+            quote!(#ty)
+        }
+    }
+
+    /// Generate the statements replacing the field with `v`, and calling the `on_change` hook
+    /// with the old and new values if the value is changed
+    fn assign_token_stream(&self) -> TokenStream {
+        let ident = &self.ident;
+        if self.readonly {
+            // The read-only fields are rejected by `try_apply`, and left unchanged by `apply`
+            quote! {
+                let _ = v;
+            }
+        } else if let Some(on_change) = &self.on_change {
+            quote! {
+                if self.#ident != v {
+                    let old = core::mem::replace(&mut self.#ident, v);
+                    #on_change(&old, &self.#ident);
+                }
+            }
+        } else {
+            quote! {
+                self.#ident = v;
+            }
+        }
+    }
+
+    /// The type of the field in the patch struct
+    fn patch_type(&self) -> TokenStream {
+        let ty = &self.ty;
+        if self.is_nesting() {
+            let patch_type = Ident::new(
+                &format!("{}Patch", &ty.to_token_stream()),
+                Span::call_site(),
+            );
+            quote!(#patch_type)
+        } else if self.special_attr.is_empty() {
+            quote!(Option<#ty>)
+        } else {
+            quote!(#ty)
+        }
+    }
+
+    #[cfg(feature = "nesting")]
+    fn is_nesting(&self) -> bool {
+        self.nesting
+    }
+
+    #[cfg(not(feature = "nesting"))]
+    fn is_nesting(&self) -> bool {
+        false
+    }
+
+    /// Generate the token stream for the Patch struct fields
+    pub fn to_token_stream(&self) -> Result<TokenStream> {
+        let Field {
+            ident,
+            ty,
+            attributes,
+            #[cfg(feature = "nesting")]
+            nesting,
+            special_attr,
+            ..
+        } = self;
+
+        let attributes = attributes
+            .iter()
+            .map(|a| {
+                quote! {
+                    #[#a]
+                }
+            })
+            .collect::<Vec<_>>();
+        match ident {
+            #[cfg(not(feature = "nesting"))]
+            Some(ident) => {
+                if !special_attr.is_empty() {
+                    Ok(quote! {
+                        #(#attributes)*
+                        pub #ident: #ty,
+                    })
+                } else {
+                    Ok(quote! {
+                        #(#attributes)*
+                        pub #ident: Option<#ty>,
+                    })
                 }
             }
             #[cfg(feature = "nesting")]
@@ -842,6 +2494,15 @@ impl Field {
         let mut field_type = None;
         let mut skip = false;
         let mut special_attr = SpecialAttr::None;
+        let mut is_empty: Option<syn::Path> = None;
+        let mut on_change = None;
+        let mut redact = false;
+        let mut readonly = false;
+        let mut group = None;
+        #[cfg(feature = "sql")]
+        let mut column = None;
+        #[cfg(any(feature = "clap", feature = "schemars"))]
+        let doc = crate::doc_text(&attrs);
 
         #[cfg(feature = "op")]
         let mut addable = Addable::Disable;
@@ -923,12 +2584,18 @@ impl Field {
                                 "`empty_value` and `skip_wrap` cannot be combined on the same field",
                             ));
                         }
-                        if let Some(lit) = crate::get_lit(path, &meta)? {
-                            special_attr = SpecialAttr::EmptyValue(lit);
-                        } else {
-                            return Err(meta
-                                .error("empty_value needs a clear value to define what is empty"));
+                        special_attr = SpecialAttr::EmptyValue(crate::EmptyValue::new(
+                            meta.value()?.parse()?,
+                        ));
+                    }
+                    IS_EMPTY => {
+                        // #[patch(is_empty = path::fn)]
+                        if is_empty.is_some() {
+                            return Err(meta.error(
+                                "The is_empty attribute can't be defined more than once",
+                            ));
                         }
+                        is_empty = Some(meta.value()?.parse()?);
                     }
                     SKIP_WRAP => {
                         // #[patch(skip_wrap)]
@@ -939,6 +2606,41 @@ impl Field {
                         }
                         special_attr = SpecialAttr::SkipWrap;
                     }
+                    ON_CHANGE => {
+                        // #[patch(on_change = path::fn)]
+                        on_change = Some(meta.value()?.parse()?);
+                    }
+                    REDACT => {
+                        // #[patch(redact)]
+                        redact = true;
+                    }
+                    READONLY => {
+                        // #[patch(readonly)]
+                        readonly = true;
+                    }
+                    GROUP => {
+                        // #[patch(group = "admin")]
+                        if group.is_some() {
+                            return Err(
+                                meta.error("The group attribute can't be defined more than once")
+                            );
+                        }
+                        group = Some(meta.value()?.parse()?);
+                    }
+                    #[cfg(feature = "sql")]
+                    COLUMN => {
+                        // #[patch(column = "user_name")]
+                        if column.is_some() {
+                            return Err(
+                                meta.error("The column attribute can't be defined more than once")
+                            );
+                        }
+                        column = crate::get_lit_str(path, &meta)?;
+                    }
+                    #[cfg(not(feature = "sql"))]
+                    COLUMN => {
+                        return Err(meta.error("#[patch(column = \"...\")] only work with `sql` feature"));
+                    }
                     _ => {
                         return Err(meta.error(format_args!(
                             "unknown patch field attribute `{}`",
@@ -953,9 +2655,48 @@ impl Field {
             }
         }
 
+        if let Some(path) = is_empty {
+            if let SpecialAttr::EmptyValue(empty) = &mut special_attr {
+                empty.is_empty = Some(path);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "`is_empty` needs `empty_value` to define the empty value",
+                ));
+            }
+        }
+
+        #[cfg(feature = "nesting")]
+        let nested_or_retyped = nesting || field_type.is_some();
+        #[cfg(not(feature = "nesting"))]
+        let nested_or_retyped = field_type.is_some();
+        #[cfg(feature = "nesting")]
+        if nesting && redact {
+            return Err(syn::Error::new_spanned(
+                &ident,
+                "`redact` can not be used on `nesting` fields, please redact the fields of the nested struct",
+            ));
+        }
+        if nested_or_retyped && readonly {
+            return Err(syn::Error::new_spanned(
+                &ident,
+                "`readonly` only works on the fields replaced as a whole, not on `nesting` or renamed fields",
+            ));
+        }
+        if nested_or_retyped {
+            if let Some(path) = &on_change {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "`on_change` only works on the fields replaced as a whole, not on `nesting` or renamed fields",
+                ));
+            }
+        }
+
         Ok(Some(Field {
             ident,
             retyped: field_type.is_some(),
+            #[cfg(feature = "alloc")]
+            struct_ty: ty.clone(),
             ty: field_type.unwrap_or(ty),
             attributes,
             #[cfg(feature = "op")]
@@ -963,10 +2704,24 @@ impl Field {
             #[cfg(feature = "nesting")]
             nesting,
             special_attr,
+            on_change,
+            redact,
+            readonly,
+            group,
+            #[cfg(feature = "sql")]
+            column,
+            #[cfg(any(feature = "clap", feature = "schemars"))]
+            doc,
         }))
     }
 }
 
+/// Returns `true` if the type is `bool`
+#[cfg(feature = "clap")]
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.qself.is_none() && p.path.is_ident("bool"))
+}
+
 trait ToStr {
     fn to_string(&self) -> String;
 }
@@ -1003,28 +2758,61 @@ mod tests {
             visibility: syn::Visibility::Public(Pub::default()),
             struct_name: syn::Ident::new("Item", Span::call_site()),
             patch_struct_name: syn::Ident::new("MyPatch", Span::call_site()),
+            #[cfg(feature = "field")]
+            field_enum_name: syn::Ident::new("ItemField", Span::call_site()),
+            #[cfg(feature = "change")]
+            change_enum_name: syn::Ident::new("ItemChange", Span::call_site()),
             generics: syn::Generics::default(),
             attributes: vec![quote! { derive(Debug, PartialEq, Clone, Serialize, Deserialize) }],
+            after_apply: None,
+            #[cfg(feature = "clap")]
+            cli: false,
+            #[cfg(feature = "audit")]
+            audit: false,
+            #[cfg(feature = "sql")]
+            sql: false,
+            #[cfg(feature = "alloc")]
+            mask_to_patch: false,
             fields: vec![
                 Field {
                     ident: Some(syn::Ident::new("field1", Span::call_site())),
                     ty: LitStr::new("SubItemPatch", Span::call_site())
                         .parse()
                         .unwrap(),
+                    #[cfg(feature = "alloc")]
+                    struct_ty: LitStr::new("SubItem", Span::call_site()).parse().unwrap(),
                     attributes: vec![],
                     retyped: true,
                     #[cfg(feature = "op")]
                     addable: Addable::Disable,
                     special_attr: SpecialAttr::None,
+                    on_change: None,
+                    redact: false,
+                    readonly: false,
+                    group: None,
+                    #[cfg(feature = "sql")]
+                    column: None,
+                    #[cfg(any(feature = "clap", feature = "schemars"))]
+                    doc: None,
                 },
                 Field {
                     ident: Some(syn::Ident::new("field3", Span::call_site())),
                     ty: LitStr::new("bool", Span::call_site()).parse().unwrap(),
+                    #[cfg(feature = "alloc")]
+                    struct_ty: LitStr::new("bool", Span::call_site()).parse().unwrap(),
                     attributes: vec![],
                     retyped: false,
                     #[cfg(feature = "op")]
                     addable: Addable::Disable,
-                    special_attr: SpecialAttr::EmptyValue(Lit::Bool(syn::LitBool::new(false, Span::call_site()))),
+                    special_attr: SpecialAttr::EmptyValue(crate::EmptyValue::new(syn::parse_quote!(false))),
+                    on_change: None,
+                    redact: false,
+                    readonly: false,
+                    group: None,
+                    #[cfg(feature = "sql")]
+                    column: None,
+                    #[cfg(any(feature = "clap", feature = "schemars"))]
+                    doc: None,
                 },
             ],
         };
@@ -0,0 +1,71 @@
+#[cfg(all(feature = "change", feature = "nesting"))]
+use struct_patch::Patch;
+
+#[allow(dead_code)]
+#[cfg(all(feature = "change", feature = "nesting"))]
+#[derive(Clone, Debug, Default, Patch, PartialEq)]
+#[patch(attribute(derive(Clone, Debug, Default)))]
+struct Config {
+    log_level: u8,
+    listen_addr: String,
+    #[patch(nesting)]
+    tls: Tls,
+}
+
+#[cfg(all(feature = "change", feature = "nesting"))]
+#[derive(Clone, Debug, Default, Patch, PartialEq)]
+#[patch(attribute(derive(Clone, Debug, Default)))]
+struct Tls {
+    enabled: bool,
+    cert_path: String,
+}
+
+// Generated by Patch derive macro with `change` feature
+//
+// enum ConfigChange {
+//     LogLevel(u8),
+//     ListenAddr(String),
+//     Tls(TlsChange),
+// }
+//
+// enum TlsChange {
+//     Enabled(bool),
+//     CertPath(String),
+// }
+
+#[cfg(not(all(feature = "change", feature = "nesting")))]
+fn main() {}
+
+#[cfg(all(feature = "change", feature = "nesting"))]
+fn main() {
+    let mut config = Config::default();
+
+    let patch = struct_patch::patch!(Config {
+        listen_addr: "0.0.0.0:8080".into(),
+        tls: { enabled: true },
+    });
+
+    let mut rebinds = Vec::new();
+    let mut tls_changes = 0;
+    config.apply_with_events(patch, |change| match change {
+        ConfigChange::LogLevel(_) => unreachable!("log level is not in the patch"),
+        ConfigChange::ListenAddr(addr) => rebinds.push(addr.clone()),
+        ConfigChange::Tls(change) => {
+            assert_eq!(change.field(), TlsField::Enabled);
+            tls_changes += 1;
+        }
+    });
+
+    assert_eq!(rebinds, vec![String::from("0.0.0.0:8080")]);
+    assert_eq!(tls_changes, 1);
+    assert_eq!(config.listen_addr, "0.0.0.0:8080");
+    assert!(config.tls.enabled);
+
+    // The changes of a patch can also be inspected without applying it
+    let patch = struct_patch::patch!(Config { log_level: 3 });
+    let fields = patch
+        .into_changes()
+        .map(|c| c.field())
+        .collect::<Vec<_>>();
+    assert_eq!(fields, vec![ConfigField::LogLevel]);
+}
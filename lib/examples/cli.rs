@@ -0,0 +1,55 @@
+#[cfg(all(feature = "clap", feature = "nesting"))]
+use clap::Parser;
+#[cfg(all(feature = "clap", feature = "nesting"))]
+use struct_patch::Patch;
+
+#[cfg(all(feature = "clap", feature = "nesting"))]
+#[derive(Debug, Patch)]
+#[patch(cli)]
+struct Config {
+    /// Verbosity of the logs
+    log_level: u8,
+    /// Print the debug information
+    debug: bool,
+    #[patch(nesting)]
+    server: Server,
+}
+
+#[cfg(all(feature = "clap", feature = "nesting"))]
+#[derive(Debug, Patch)]
+#[patch(cli)]
+struct Server {
+    /// Port to listen on
+    port: u16,
+}
+
+// Generated options of ConfigPatch by Patch derive macro with `#[patch(cli)]`
+//
+//       --log-level <LOG_LEVEL>  Verbosity of the logs
+//       --debug                  Print the debug information
+//       --server-port <PORT>     Port to listen on
+
+#[cfg(all(feature = "clap", feature = "nesting"))]
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigPatch,
+}
+
+#[cfg(not(all(feature = "clap", feature = "nesting")))]
+fn main() {}
+
+#[cfg(all(feature = "clap", feature = "nesting"))]
+fn main() {
+    // NOTE:
+    // The default values stay in the `Default`-like constructor of the config, and the command
+    // line only overrides the options which are passed
+    let mut config = Config {
+        log_level: 10,
+        debug: false,
+        server: Server { port: 8080 },
+    };
+    config.apply(Cli::parse().config);
+
+    println!("{config:#?}")
+}
@@ -3,7 +3,6 @@ fn main() {
     use std::collections::{
         BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque,
     };
-    use std::iter::{Extend, IntoIterator};
     use struct_patch::Filler;
 
     #[derive(Clone, Default, Filler)]
@@ -12,7 +12,7 @@ struct WrapVec {
 
 impl Extend<usize> for WrapVec {
     fn extend<WrapVec: IntoIterator<Item = usize>>(&mut self, iter: WrapVec) {
-        self.inner.extend(iter.into_iter());
+        self.inner.extend(iter);
     }
 }
 
@@ -56,6 +56,7 @@ struct Item {
 //
 // #[derive(Debug, Default)] // pass by filler(attribute(...))
 // struct ItemFiller {
+//     field_int: usize,
 //     maybe_field_int: Option<usize>,
 //     maybe_field_string: Option<String>,
 //     list: Vec<usize>,
@@ -83,7 +84,7 @@ fn main() {
 
     assert_eq!(
         format!("{filler:?}"),
-        "ItemFiller { field_int: 0, maybe_field_int: Some(7), maybe_field_string: None, list: [], _deque: [], _linked_list: [], _set: {}, _bset: {}, _heap: [], _wrap: WrapVec { inner: [] } }"
+        "ItemFiller { field_int: 0, maybe_field_int: Some(7), maybe_field_string: None, list: [], _deque: [], _linked_list: [], _map: {}, _bmap: {}, _set: {}, _bset: {}, _heap: [], _wrap: WrapVec { inner: [] } }"
     );
 
     item.apply(filler);
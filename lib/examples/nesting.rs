@@ -1,6 +1,6 @@
 use serde::Deserialize;
 use struct_patch::Patch;
-#[cfg(feature = "status")]
+#[cfg(all(feature = "nesting", feature = "status"))]
 use struct_patch::Status;
 
 #[allow(dead_code)]
@@ -51,6 +51,15 @@ fn main() {
     }"#;
     assert_eq!(patch, serde_json::from_str(data).unwrap());
 
+    // The same sparse patch can be written with the `patch!` macro
+    assert_eq!(
+        patch,
+        struct_patch::patch!(Item {
+            field_int: 7,
+            inner: { inner_int: 100 },
+        })
+    );
+
     #[cfg(feature = "status")]
     assert!(!patch.is_empty());
 
@@ -0,0 +1,245 @@
+#![cfg(feature = "audit")]
+//! Record the changes made by patches, to persist and replay them
+use crate::traits::Patch;
+
+extern crate std;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::string::String;
+use std::time::SystemTime;
+use std::vec::Vec;
+
+/// The change of one field made by a patch
+///
+/// The values are the JSON values of the field, the values of `#[patch(redact)]` fields are
+/// recorded as `null`, and only the fact that they changed is kept.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// The path of the field, the fields of the nesting fields are joined with dots
+    pub field: String,
+    /// The value before the patch is applied
+    pub before: Value,
+    /// The value after the patch is applied
+    pub after: Value,
+    /// `true` if the values are hidden by `#[patch(redact)]`
+    #[serde(default)]
+    pub redacted: bool,
+    /// When the patch was applied
+    pub at: SystemTime,
+}
+
+/// A struct whose fields can be read and written by the paths of the audit records, implemented
+/// by the structs deriving `Patch` with `#[patch(audit)]`
+///
+/// The field types need to implement `Serialize` and `Deserialize`, and the `nesting` fields need
+/// `#[patch(audit)]` as well.  The renamed fields, `#[patch(name = "...")]`, are recorded with
+/// the whole value of the field.
+/// ```rust
+/// # use struct_patch::Patch;
+/// # use struct_patch::audit::Audited;
+/// #[derive(Clone, Debug, PartialEq, Patch)]
+/// #[patch(audit)]
+/// struct Config {
+///     log_level: u8,
+///     #[patch(redact)]
+///     token: String,
+/// }
+///
+/// let mut config = Config { log_level: 0, token: String::from("a") };
+/// let records = config.apply_audited(struct_patch::patch!(Config {
+///     log_level: 3,
+///     token: String::from("b"),
+/// }));
+/// assert_eq!(records[0].field, "log_level");
+/// assert_eq!((records[0].before.as_u64(), records[0].after.as_u64()), (Some(0), Some(3)));
+/// assert!(records[1].redacted && records[1].after.is_null());
+///
+/// // The redacted values can not be replayed
+/// let mut replayed = Config { log_level: 0, token: String::from("a") };
+/// replayed.replay(&records).unwrap();
+/// assert_eq!(replayed.log_level, 3);
+/// assert_eq!(replayed.token, "a");
+/// ```
+pub trait Audited {
+    #[doc(hidden)]
+    fn audit_value(&self, path: &str) -> Option<(Value, bool)>;
+
+    #[doc(hidden)]
+    fn audit_restore(&mut self, path: &str, value: Value) -> Option<serde_json::Result<()>>;
+
+    /// Set the fields to the `after` values of the records in order, the redacted records and
+    /// the records of unknown fields are skipped
+    fn replay(&mut self, records: &[AuditRecord]) -> serde_json::Result<()> {
+        for record in records.iter().filter(|r| !r.redacted) {
+            if let Some(result) = self.audit_restore(&record.field, record.after.clone()) {
+                result?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Apply the patch, and record the fields of the paths whose values are changed
+#[doc(hidden)]
+pub fn apply_audited<T, P>(value: &mut T, patch: P, paths: Vec<String>) -> Vec<AuditRecord>
+where
+    T: Audited + Patch<P>,
+{
+    let at = SystemTime::now();
+    let before: Vec<_> = paths.iter().map(|p| value.audit_value(p)).collect();
+    value.apply(patch);
+    paths
+        .into_iter()
+        .zip(before)
+        .filter_map(|(field, before)| {
+            let (before, redacted) = before?;
+            let (after, _) = value.audit_value(&field)?;
+            if before == after {
+                return None;
+            }
+            let (before, after) = if redacted {
+                (Value::Null, Value::Null)
+            } else {
+                (before, after)
+            };
+            Some(AuditRecord {
+                field,
+                before,
+                after,
+                redacted,
+                at,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as struct_patch;
+    use crate::Patch;
+
+    #[derive(Clone, Debug, PartialEq, Patch, Serialize, Deserialize)]
+    #[patch(audit, attribute(derive(Default)))]
+    struct Database {
+        url: String,
+        #[patch(redact)]
+        password: String,
+        pool: Option<u32>,
+    }
+
+    #[test]
+    fn test_audit() {
+        let mut db = Database {
+            url: String::from("db:1"),
+            password: String::from("a"),
+            pool: Some(4),
+        };
+        let records = db.apply_audited(DatabasePatch {
+            url: Some(String::from("db:1")),
+            password: Some(String::from("b")),
+            pool: Some(None),
+        });
+        // the unchanged url is not recorded
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].field, "password");
+        assert_eq!(records[0].before, Value::Null);
+        assert!(records[0].redacted);
+        assert_eq!(records[1].field, "pool");
+        assert_eq!(records[1].before, Value::from(4));
+        assert_eq!(records[1].after, Value::Null);
+        assert_eq!(records[0].at, records[1].at);
+
+        // the records are persisted and replayed
+        let json = serde_json::to_string(&records).unwrap();
+        let records: Vec<AuditRecord> = serde_json::from_str(&json).unwrap();
+        let mut replayed = Database {
+            url: String::from("db:1"),
+            password: String::from("a"),
+            pool: Some(4),
+        };
+        replayed.replay(&records).unwrap();
+        assert_eq!(replayed.pool, None);
+        assert_eq!(replayed.password, "a");
+
+        assert!(db.apply_audited(DatabasePatch::default()).is_empty());
+    }
+
+    #[cfg(feature = "nesting")]
+    #[test]
+    fn test_audit_nesting() {
+        #[derive(Clone, Debug, PartialEq, Patch, Serialize, Deserialize)]
+        #[patch(audit, attribute(derive(Default)))]
+        struct Config {
+            name: String,
+            #[patch(nesting)]
+            database: Database,
+        }
+
+        let config = || Config {
+            name: String::from("a"),
+            database: Database {
+                url: String::from("db:1"),
+                password: String::new(),
+                pool: None,
+            },
+        };
+        let mut patched = config();
+        let records = patched.apply_audited(ConfigPatch {
+            database: DatabasePatch {
+                url: Some(String::from("db:2")),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].field, "database.url");
+        assert_eq!(records[0].before, Value::from("db:1"));
+        assert_eq!(records[0].after, Value::from("db:2"));
+
+        let mut replayed = config();
+        let mut unknown = records[0].clone();
+        unknown.field = String::from("database.unknown");
+        replayed.replay(&[unknown]).unwrap();
+        assert_eq!(replayed, config());
+        replayed.replay(&records).unwrap();
+        assert_eq!(replayed, patched);
+
+        let mut invalid = records[0].clone();
+        invalid.after = Value::from(1);
+        assert!(replayed.replay(&[invalid]).is_err());
+    }
+
+    #[test]
+    fn test_audit_renamed() {
+        #[derive(Clone, Debug, PartialEq, Patch, Serialize, Deserialize)]
+        #[patch(audit, attribute(derive(Default)))]
+        struct Service {
+            #[patch(name = "DatabasePatch")]
+            database: Database,
+        }
+
+        let service = || Service {
+            database: Database {
+                url: String::from("db:1"),
+                password: String::new(),
+                pool: None,
+            },
+        };
+        let mut patched = service();
+        let records = patched.apply_audited(ServicePatch {
+            database: Some(DatabasePatch {
+                pool: Some(Some(2)),
+                ..Default::default()
+            }),
+        });
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].field, "database");
+        assert_eq!(records[0].before["pool"], Value::Null);
+        assert_eq!(records[0].after["pool"], Value::from(2));
+
+        let mut replayed = service();
+        replayed.replay(&records).unwrap();
+        assert_eq!(replayed, patched);
+    }
+}
@@ -58,4 +58,33 @@ mod tests {
             }
         );
     }
+    #[test]
+    fn test_patch_box_hooks() {
+        fn recompute(item: &mut Item) {
+            item.other_len = item.other.len();
+        }
+
+        #[derive(Patch, Debug, PartialEq)]
+        #[patch(after_apply = recompute)]
+        struct Item {
+            other: String,
+            #[patch(skip)]
+            other_len: usize,
+        }
+
+        let mut item = Item {
+            other: String::from("hello"),
+            other_len: 5,
+        };
+        item.apply(Box::new(ItemPatch {
+            other: Some(String::from("bye")),
+        }));
+        assert_eq!(
+            item,
+            Item {
+                other: String::from("bye"),
+                other_len: 3
+            }
+        );
+    }
 }
@@ -0,0 +1,68 @@
+//! The errors returned by the generated methods, and how they are handled
+use core::fmt;
+
+/// The error of a patch setting a `#[patch(readonly)]` field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadOnly {
+    /// The name of the field
+    pub field: &'static str,
+}
+
+impl fmt::Display for ReadOnly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the field `{}` is read-only", self.field)
+    }
+}
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadOnly {}
+
+/// The error of a patch setting a field of a `#[patch(group = "...")]` group which the caller is
+/// not allowed to change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Forbidden {
+    /// The name of the field
+    pub field: &'static str,
+    /// The group needed to change the field
+    pub group: &'static str,
+}
+
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the field `{}` can only be changed by the `{}` group",
+            self.field, self.group
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Forbidden {}
+
+/// The error of `ensure_filled`, listing the fields which are still empty after the fillers are
+/// applied
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unfilled {
+    /// The names of the fields, the fields of the nested structs are joined to the nesting fields
+    /// with dots
+    pub fields: alloc::vec::Vec<alloc::string::String>,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for Unfilled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the fields are not filled:")?;
+        for field in self.fields.iter() {
+            write!(f, " `{}`", field)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Unfilled {}
@@ -78,6 +78,41 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+/// Build a patch with only the given fields set, all other fields are left empty
+///
+/// The values are given without the `Option` wrapper of the patch struct, and the fields of a
+/// `#[patch(nesting)]` field are given in a nested block. Unknown fields are compile errors.
+/// ```rust
+/// # use struct_patch::Patch;
+/// #[derive(Default, Patch)]
+/// #[patch(attribute(derive(Debug, PartialEq)))]
+/// struct Item {
+///     field_bool: bool,
+///     field_int: usize,
+///     field_string: String,
+/// }
+///
+/// let patch = struct_patch::patch!(Item { field_int: 7 });
+/// assert_eq!(
+///     patch,
+///     ItemPatch {
+///         field_bool: None,
+///         field_int: Some(7),
+///         field_string: None,
+///     }
+/// );
+/// ```
+///
+/// ```compile_fail
+/// # use struct_patch::Patch;
+/// #[derive(Patch)]
+/// struct Item {
+///     field_int: usize,
+/// }
+///
+/// let patch = struct_patch::patch!(Item { field_itn: 7 });
+/// ```
+pub use struct_patch_derive::patch;
 #[cfg(feature = "catalyst")]
 #[doc(hidden)]
 pub use struct_patch_derive::Catalyst;
@@ -88,10 +123,129 @@ pub use struct_patch_derive::Patch;
 #[cfg(feature = "catalyst")]
 #[doc(hidden)]
 pub use struct_patch_derive::Substrate;
+pub mod audit;
 pub mod r#box;
+pub mod error;
+pub mod merge;
+#[cfg(feature = "merge")]
+pub use merge::{Concat, Replace};
 pub mod option;
+pub mod shared;
+pub mod sql;
+#[cfg(all(feature = "std", feature = "field"))]
+pub use shared::{Notification, Shared};
 pub mod traits;
 pub use traits::*;
+pub mod watch;
+#[cfg(feature = "watch")]
+pub use watch::{FileSource, Reloader};
+
+/// The patch turning `previous` into `current`
+///
+/// Both values are cloned, as [`Patch::into_patch_by_diff`] consumes them, use [`diff_owned`] to
+/// avoid the clones when the values are no longer needed.
+/// ```rust
+/// # use struct_patch::Patch;
+/// #[derive(Clone, Patch)]
+/// struct Item {
+///     field_bool: bool,
+///     field_int: usize,
+/// }
+///
+/// let previous = Item { field_bool: true, field_int: 1 };
+/// let current = Item { field_bool: true, field_int: 7 };
+/// let patch = struct_patch::diff(&previous, &current);
+/// assert_eq!(patch.field_bool, None);
+/// assert_eq!(patch.field_int, Some(7));
+/// ```
+pub fn diff<T: Patchable + Clone>(previous: &T, current: &T) -> T::Patch {
+    diff_owned(previous.clone(), current.clone())
+}
+
+/// The patch turning `previous` into `current`, consuming both values
+pub fn diff_owned<T: Patchable>(previous: T, current: T) -> T::Patch {
+    current.into_patch_by_diff(previous)
+}
+
+/// The empty patch of `T`, which changes nothing when applied
+pub fn empty<T: Patchable>() -> T::Patch {
+    T::new_empty_patch()
+}
+
+/// The empty filler of `T`, which fills nothing when applied
+pub fn empty_filler<T: HasFiller>() -> T::Filler {
+    T::new_empty_filler()
+}
+
+#[doc(hidden)]
+pub mod __private {
+    /// Printed by the `Debug` impls in place of the redacted values
+    pub struct Redacted;
+
+    impl core::fmt::Debug for Redacted {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str("<redacted>")
+        }
+    }
+
+    /// The `is_empty` predicates of the `empty_value` fields, taking the value by reference,
+    /// `fn(&T) -> bool`, or by value for the `Copy` types, `fn(T) -> bool` as `f64::is_nan`
+    pub trait IsEmpty<T, M> {
+        fn check(self, value: &T) -> bool;
+    }
+
+    pub struct ByRef;
+
+    pub struct ByValue;
+
+    impl<T, F: FnOnce(&T) -> bool> IsEmpty<T, ByRef> for F {
+        fn check(self, value: &T) -> bool {
+            self(value)
+        }
+    }
+
+    impl<T: Copy, F: FnOnce(T) -> bool> IsEmpty<T, ByValue> for F {
+        fn check(self, value: &T) -> bool {
+            self(*value)
+        }
+    }
+
+    /// Check the value with the `is_empty` predicate of the field
+    pub fn is_empty<T, M>(predicate: impl IsEmpty<T, M>, value: &T) -> bool {
+        predicate.check(value)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "alloc")]
+    pub use alloc::borrow::Cow;
+    #[cfg(feature = "alloc")]
+    pub use alloc::boxed::Box;
+    #[cfg(feature = "alloc")]
+    pub use alloc::rc::Rc;
+    #[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+    pub use alloc::sync::Arc;
+    #[cfg(feature = "clap")]
+    pub use clap;
+    #[cfg(feature = "schemars")]
+    pub use schemars;
+    #[cfg(feature = "audit")]
+    pub use serde;
+    #[cfg(any(feature = "schemars", feature = "audit"))]
+    pub use serde_json;
+
+    #[cfg(feature = "alloc")]
+    pub use alloc::string::String;
+
+    /// The name of a field or a command line option, prefixed with the names of the nesting
+    /// fields
+    #[cfg(feature = "alloc")]
+    pub fn prefixed(prefix: &str, name: &str) -> alloc::string::String {
+        let mut id = alloc::string::String::from(prefix);
+        id.push_str(name);
+        id
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -150,6 +304,69 @@ mod tests {
         assert!(!patch.is_empty());
     }
 
+    #[test]
+    fn test_empty_value() {
+        use core::time::Duration;
+        use struct_patch::Filler;
+
+        #[derive(Debug, Default, PartialEq, Patch, Filler)]
+        #[patch(attribute(derive(Debug, PartialEq)))]
+        struct Item {
+            #[patch(empty_value = u32::MAX)]
+            #[filler(empty_value = u32::MAX)]
+            limit: u32,
+            // the predicates take the value by reference, or by value for `Copy` types
+            #[patch(empty_value = Duration::ZERO, is_empty = Duration::is_zero)]
+            timeout: Duration,
+            #[patch(empty_value = f64::NAN, is_empty = f64::is_nan)]
+            #[filler(empty_value = f64::NAN, is_empty = f64::is_nan)]
+            ratio: f64,
+            #[patch(empty_value = -1)]
+            offset: i8,
+        }
+
+        let patch: ItemPatch = Item::new_empty_patch();
+        assert_eq!(patch.limit, u32::MAX);
+        assert!(patch.ratio.is_nan());
+        #[cfg(feature = "status")]
+        {
+            use struct_patch::Status;
+            assert!(patch.is_empty());
+            let mut patch: ItemPatch = Item::new_empty_patch();
+            patch.ratio = 0.5;
+            assert!(!patch.is_empty());
+        }
+
+        let mut item = Item::default();
+        // `Patch::apply` is called explicitly, `Filler` is also in scope
+        Patch::apply(
+            &mut item,
+            ItemPatch {
+                limit: 3,
+                timeout: Duration::ZERO,
+                ratio: f64::NAN,
+                offset: 0,
+            },
+        );
+        assert_eq!(
+            item,
+            Item {
+                limit: 3,
+                ..Default::default()
+            }
+        );
+
+        let mut item = Item {
+            limit: u32::MAX,
+            ratio: f64::NAN,
+            ..Default::default()
+        };
+        let mut filler = Item::new_empty_filler();
+        filler.ratio = 0.5;
+        Filler::apply(&mut item, filler);
+        assert_eq!((item.limit, item.ratio), (u32::MAX, 0.5));
+    }
+
     #[test]
     fn test_derive() {
         #[allow(dead_code)]
@@ -219,6 +436,224 @@ mod tests {
         assert_eq!(item, Item { id: 1, data: 15 });
     }
 
+    #[test]
+    fn test_readonly() {
+        use alloc::string::ToString;
+        use struct_patch::error::ReadOnly;
+
+        #[derive(Patch, PartialEq, Debug)]
+        #[patch(attribute(derive(PartialEq, Debug, Deserialize)))]
+        #[patch(attribute(serde(deny_unknown_fields)))]
+        struct Item {
+            #[patch(readonly)]
+            id: u32,
+            data: u32,
+        }
+
+        let mut item = Item { id: 1, data: 2 };
+        let data = r#"{ "id": 10, "data": 15 }"#;
+        let patch: ItemPatch = serde_json::from_str(data).unwrap();
+        assert_eq!(
+            patch,
+            ItemPatch {
+                id: Some(10),
+                data: Some(15)
+            }
+        );
+
+        let err = item.try_apply(patch).unwrap_err();
+        assert_eq!(err, ReadOnly { field: "id" });
+        assert_eq!(err.to_string(), "the field `id` is read-only");
+        assert_eq!(item, Item { id: 1, data: 2 });
+
+        let patch: ItemPatch = serde_json::from_str(r#"{ "data": 15 }"#).unwrap();
+        assert_eq!(patch.check_readonly(), Ok(()));
+        item.try_apply(patch).unwrap();
+        assert_eq!(item, Item { id: 1, data: 15 });
+
+        // `apply` leaves the read-only fields unchanged
+        item.apply(ItemPatch {
+            id: Some(10),
+            data: Some(20),
+        });
+        assert_eq!(item, Item { id: 1, data: 20 });
+
+        assert!(serde_json::from_str::<ItemPatch>(r#"{ "idd": 10 }"#).is_err());
+
+        #[cfg(feature = "nesting")]
+        {
+            #[derive(Patch, PartialEq, Debug)]
+            #[patch(attribute(derive(PartialEq, Debug, Deserialize)))]
+            struct Outer {
+                #[patch(nesting)]
+                item: Item,
+            }
+
+            let mut outer = Outer { item };
+            let patch: OuterPatch = serde_json::from_str(r#"{ "item": { "id": 10 } }"#).unwrap();
+            assert_eq!(outer.try_apply(patch), Err(ReadOnly { field: "id" }));
+            assert_eq!(outer.item.id, 1);
+        }
+    }
+
+    #[test]
+    fn test_authorize() {
+        use struct_patch::error::Forbidden;
+
+        #[derive(Patch, PartialEq, Debug)]
+        #[patch(attribute(derive(Clone, PartialEq, Debug)))]
+        struct Account {
+            name: String,
+            #[patch(group = "admin")]
+            quota: u32,
+            #[patch(group = "billing")]
+            plan: Option<String>,
+        }
+
+        let account = || Account {
+            name: String::from("a"),
+            quota: 1,
+            plan: None,
+        };
+        let patch = || AccountPatch {
+            name: Some(String::from("b")),
+            quota: Some(2),
+            plan: None,
+        };
+        let mut item = account();
+        assert_eq!(
+            item.apply_authorized(patch(), &["billing"]),
+            Err(Forbidden {
+                field: "quota",
+                group: "admin"
+            })
+        );
+        assert_eq!(item, account());
+
+        let mut stripped = patch();
+        stripped.strip_unauthorized(&["billing"]);
+        item.apply_authorized(stripped, &["billing"]).unwrap();
+        assert_eq!(item.name, "b");
+        assert_eq!(item.quota, 1);
+
+        item.apply_authorized(patch(), &["admin"]).unwrap();
+        assert_eq!(item.quota, 2);
+
+        let mut patch = patch();
+        patch.strip_unauthorized(&[]);
+        assert_eq!(
+            patch,
+            AccountPatch {
+                name: Some(String::from("b")),
+                quota: None,
+                plan: None,
+            }
+        );
+
+        #[cfg(feature = "nesting")]
+        {
+            #[derive(Patch, PartialEq, Debug)]
+            #[patch(attribute(derive(Clone, PartialEq, Debug)))]
+            struct Tenant {
+                #[patch(nesting, group = "owner")]
+                account: Account,
+            }
+
+            let mut tenant = Tenant { account: account() };
+            let patch = TenantPatch {
+                account: AccountPatch {
+                    name: Some(String::from("b")),
+                    quota: None,
+                    plan: Some(Some(String::from("pro"))),
+                },
+            };
+            // the fields of a nesting field need the group of the nesting field as well
+            let mut stripped = patch.clone();
+            stripped.strip_unauthorized(&["billing"]);
+            assert_eq!(stripped.account.name, None);
+            assert_eq!(stripped.account.plan, None);
+            assert_eq!(
+                tenant.apply_authorized(patch.clone(), &["owner"]),
+                Err(Forbidden {
+                    field: "plan",
+                    group: "billing"
+                })
+            );
+            tenant
+                .apply_authorized(patch, &["owner", "billing"])
+                .unwrap();
+            assert_eq!(tenant.account.plan.as_deref(), Some("pro"));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_field_mask() {
+        #[derive(Patch, Clone, PartialEq, Debug)]
+        #[patch(attribute(derive(PartialEq, Debug)), mask_to_patch)]
+        struct Address {
+            city: String,
+            zip: Option<u32>,
+        }
+
+        let address = Address {
+            city: String::from("Taipei"),
+            zip: None,
+        };
+        let patch = AddressPatch {
+            city: None,
+            zip: Some(None),
+        };
+        assert_eq!(patch.field_mask(), ["zip"]);
+        assert_eq!(
+            address.mask_to_patch(&["zip", "unknown", "city.name"]),
+            patch
+        );
+
+        #[cfg(feature = "nesting")]
+        {
+            #[derive(Patch, Clone, PartialEq, Debug)]
+            #[patch(attribute(derive(PartialEq, Debug)), mask_to_patch)]
+            struct User {
+                name: String,
+                #[patch(nesting)]
+                address: Address,
+            }
+
+            let user = User {
+                name: String::from("a"),
+                address,
+            };
+            let patch = user.mask_to_patch(&["name", "address.city"]);
+            assert_eq!(
+                patch,
+                UserPatch {
+                    name: Some(String::from("a")),
+                    address: AddressPatch {
+                        city: Some(String::from("Taipei")),
+                        zip: None,
+                    },
+                }
+            );
+            assert_eq!(patch.field_mask(), ["name", "address.city"]);
+
+            // the bare path of a nesting field selects all its fields
+            let patch = user.mask_to_patch(&["address"]);
+            assert_eq!(patch.field_mask(), ["address.city", "address.zip"]);
+
+            let mut other = User {
+                name: String::from("b"),
+                address: Address {
+                    city: String::new(),
+                    zip: Some(1),
+                },
+            };
+            other.apply(patch);
+            assert_eq!(other.name, "b");
+            assert_eq!(other.address, user.address);
+        }
+    }
+
     #[test]
     fn test_nested() {
         #[derive(PartialEq, Debug, Default, Patch, Deserialize)]
@@ -334,6 +769,332 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_patch_literal() {
+        #[derive(Patch)]
+        #[patch(attribute(derive(PartialEq, Debug)))]
+        struct Item {
+            field: u32,
+            other: Option<String>,
+            #[patch(empty_value = 0)]
+            count: u32,
+            #[patch(skip_wrap)]
+            tags: Option<alloc::vec::Vec<u32>>,
+        }
+
+        assert_eq!(
+            struct_patch::patch!(Item {}),
+            ItemPatch {
+                field: None,
+                other: None,
+                count: 0,
+                tags: None,
+            }
+        );
+        assert_eq!(
+            struct_patch::patch!(Item {
+                field: 1,
+                other: None,
+                count: 2,
+                tags: Some(alloc::vec![3]),
+            }),
+            ItemPatch {
+                field: Some(1),
+                other: Some(None),
+                count: 2,
+                tags: Some(alloc::vec![3]),
+            }
+        );
+    }
+
+    #[cfg(feature = "field")]
+    #[test]
+    fn test_field() {
+        #[derive(Patch)]
+        #[patch(attribute(derive(PartialEq, Debug)))]
+        struct Item {
+            field: u32,
+            other_field: Option<String>,
+            #[patch(empty_value = 0)]
+            count: u32,
+        }
+
+        assert_eq!(
+            ItemField::ALL,
+            &[ItemField::Field, ItemField::OtherField, ItemField::Count]
+        );
+        assert_eq!(ItemField::OtherField.name(), "other_field");
+
+        let mut patch = ItemPatch {
+            field: Some(1),
+            other_field: Some(None),
+            count: 2,
+        };
+        assert!(patch.fields().eq([ItemField::Field, ItemField::OtherField, ItemField::Count]));
+
+        patch.clear(ItemField::Field);
+        assert!(!patch.is_set(ItemField::Field));
+        patch.retain(|f| f != ItemField::Count);
+        assert_eq!(
+            patch,
+            ItemPatch {
+                field: None,
+                other_field: Some(None),
+                count: 0,
+            }
+        );
+
+        let mut patch = ItemPatch {
+            field: Some(1),
+            other_field: None,
+            count: 2,
+        };
+        patch.only(&[ItemField::Count]);
+        assert!(patch.fields().eq([ItemField::Count]));
+    }
+
+    #[cfg(all(feature = "field", feature = "alloc"))]
+    #[test]
+    fn test_field_split() {
+        #[derive(Patch)]
+        #[patch(attribute(derive(PartialEq, Debug)))]
+        struct Item {
+            field: u32,
+            other: u32,
+            #[patch(empty_value = 0)]
+            count: u32,
+        }
+
+        let patch = ItemPatch {
+            field: Some(1),
+            other: None,
+            count: 2,
+        };
+        assert_eq!(
+            patch.split(),
+            alloc::vec![
+                ItemPatch {
+                    field: Some(1),
+                    other: None,
+                    count: 0,
+                },
+                ItemPatch {
+                    field: None,
+                    other: None,
+                    count: 2,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "change")]
+    #[test]
+    fn test_change() {
+        #[derive(Patch, Clone, Debug, PartialEq)]
+        #[patch(attribute(derive(Clone, Debug, PartialEq)))]
+        struct B {
+            c: u32,
+        }
+
+        #[derive(Patch, Debug, PartialEq)]
+        #[patch(attribute(derive(Clone)))]
+        struct A {
+            a: u32,
+            #[patch(name = "BPatch")]
+            b: B,
+            #[patch(skip_wrap)]
+            tags: Option<alloc::vec::Vec<u32>>,
+        }
+
+        let mut item = A {
+            a: 0,
+            b: B { c: 0 },
+            tags: None,
+        };
+        let patch = APatch {
+            a: Some(1),
+            b: Some(BPatch { c: Some(2) }),
+            tags: Some(alloc::vec![3]),
+        };
+
+        let mut fields = alloc::vec::Vec::new();
+        item.apply_with_events(patch, |change| {
+            match change {
+                AChange::A(a) => assert_eq!(*a, 1),
+                AChange::B(b) => assert_eq!(*b, BPatch { c: Some(2) }),
+                AChange::Tags(tags) => assert_eq!(*tags, Some(alloc::vec![3])),
+            }
+            fields.push(change.field());
+        });
+        assert_eq!(fields, [AField::A, AField::B, AField::Tags]);
+        assert_eq!(
+            item,
+            A {
+                a: 1,
+                b: B { c: 2 },
+                tags: Some(alloc::vec![3]),
+            }
+        );
+
+        let patch = APatch {
+            a: None,
+            b: None,
+            tags: None,
+        };
+        assert_eq!(patch.into_changes().count(), 0);
+    }
+
+    #[test]
+    fn test_hooks() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static CHANGES: AtomicU32 = AtomicU32::new(0);
+
+        fn on_pattern_change(old: &String, new: &String) {
+            assert_ne!(old, new);
+            CHANGES.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn recompute(item: &mut Item) {
+            item.pattern_len = item.pattern.len();
+        }
+
+        #[derive(Patch, Debug, PartialEq)]
+        #[patch(after_apply = recompute)]
+        struct Item {
+            #[patch(on_change = on_pattern_change)]
+            pattern: String,
+            #[patch(skip)]
+            pattern_len: usize,
+        }
+
+        let mut item = Item {
+            pattern: String::from("a"),
+            pattern_len: 1,
+        };
+        item.apply(ItemPatch {
+            pattern: Some(String::from("a")),
+        });
+        assert_eq!(CHANGES.load(Ordering::SeqCst), 0);
+
+        item.apply(ItemPatch {
+            pattern: Some(String::from("abc")),
+        });
+        assert_eq!(CHANGES.load(Ordering::SeqCst), 1);
+        assert_eq!(item.pattern_len, 3);
+
+        #[cfg(feature = "op")]
+        {
+            let item = item
+                << ItemPatch {
+                    pattern: Some(String::from("abcd")),
+                };
+            assert_eq!(CHANGES.load(Ordering::SeqCst), 2);
+            assert_eq!(item.pattern_len, 4);
+        }
+    }
+
+    #[cfg(all(feature = "field", feature = "nesting"))]
+    #[test]
+    fn test_is_changed_by() {
+        use struct_patch::Patchable;
+
+        #[derive(Patch, Default)]
+        struct Inner {
+            a: u32,
+        }
+
+        #[derive(Patch, Default)]
+        struct Outer {
+            #[patch(empty_value = 0)]
+            count: u32,
+            #[patch(skip_wrap)]
+            name: Option<&'static str>,
+            #[patch(nesting)]
+            inner: Inner,
+        }
+
+        let outer = Outer {
+            count: 1,
+            name: Some("a"),
+            inner: Inner { a: 2 },
+        };
+        let changed = |patch: OuterPatch| {
+            OuterField::ALL
+                .iter()
+                .copied()
+                .filter(|f| outer.is_changed_by(&patch, *f))
+                .collect::<alloc::vec::Vec<_>>()
+        };
+        assert!(changed(struct_patch::patch!(Outer {
+            count: 1,
+            name: Some("a"),
+            inner: { a: 2 },
+        }))
+        .is_empty());
+        assert_eq!(
+            changed(struct_patch::patch!(Outer {
+                count: 3,
+                name: Some("b"),
+                inner: { a: 4 },
+            })),
+            [OuterField::Count, OuterField::Name, OuterField::Inner]
+        );
+    }
+
+    #[cfg(all(feature = "change", feature = "nesting"))]
+    #[test]
+    fn test_change_hooks() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static OUTER: AtomicU32 = AtomicU32::new(0);
+        static INNER: AtomicU32 = AtomicU32::new(0);
+
+        fn outer_applied(_: &mut Outer) {
+            OUTER.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn inner_applied(_: &mut Inner) {
+            INNER.fetch_add(1, Ordering::SeqCst);
+        }
+
+        // Not `Clone`, the changes are passed to the sink by reference
+        #[derive(Debug, Default, PartialEq)]
+        struct Token(u32);
+
+        #[derive(Patch, Default)]
+        #[patch(after_apply = inner_applied)]
+        struct Inner {
+            a: u32,
+            b: u32,
+        }
+
+        #[derive(Patch, Default)]
+        #[patch(after_apply = outer_applied)]
+        struct Outer {
+            token: Token,
+            #[patch(nesting)]
+            inner: Inner,
+        }
+
+        let mut outer = Outer::default();
+        let patch = struct_patch::patch!(Outer {
+            token: Token(1),
+            inner: { a: 2, b: 3 },
+        });
+        let mut changes = 0;
+        outer.apply_with_events(patch, |_| changes += 1);
+        assert_eq!(changes, 3);
+        assert_eq!((outer.token.0, outer.inner.a, outer.inner.b), (1, 2, 3));
+        assert_eq!(OUTER.load(Ordering::SeqCst), 1);
+        assert_eq!(INNER.load(Ordering::SeqCst), 1);
+
+        outer.apply_change(OuterChange::Token(Token(4)));
+        assert_eq!(outer.token, Token(4));
+        assert_eq!(OUTER.load(Ordering::SeqCst), 2);
+        assert_eq!(INNER.load(Ordering::SeqCst), 2);
+    }
+
     #[cfg(feature = "op")]
     #[test]
     fn test_shl() {
@@ -533,4 +1294,849 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_redact() {
+        use alloc::format;
+
+        #[derive(Clone, Debug, PartialEq, Patch)]
+        #[patch(attribute(derive(Clone, Debug, Default)))]
+        struct Config {
+            user: String,
+            #[patch(redact)]
+            password: String,
+            #[patch(redact, skip_wrap)]
+            token: Option<String>,
+        }
+
+        let patch = ConfigPatch {
+            user: Some(String::from("admin")),
+            password: Some(String::from("hunter2")),
+            token: None,
+        };
+        assert_eq!(
+            format!("{:?}", patch),
+            r#"ConfigPatch { user: Some("admin"), password: Some(<redacted>), token: None }"#
+        );
+
+        let previous = Config {
+            user: String::from("admin"),
+            password: String::new(),
+            token: None,
+        };
+        let config = Config {
+            user: String::from("admin"),
+            password: String::from("hunter2"),
+            token: Some(String::from("secret")),
+        };
+        let diff: ConfigPatch = config.into_patch_by_diff(previous);
+        assert_eq!(
+            format!("{:#?}", diff),
+            "ConfigPatch {\n    user: None,\n    password: Some(\n        <redacted>,\n    ),\n    token: Some(\n        <redacted>,\n    ),\n}"
+        );
+
+        #[cfg(feature = "change")]
+        assert_eq!(
+            format!("{:?}", patch.into_changes().collect::<alloc::vec::Vec<_>>()),
+            r#"[User("admin"), Password(<redacted>)]"#
+        );
+    }
+
+    #[cfg(all(feature = "clap", feature = "nesting"))]
+    #[test]
+    fn test_cli() {
+        use alloc::string::ToString;
+
+        #[derive(Debug, PartialEq, Patch)]
+        #[patch(cli, attribute(derive(Debug, PartialEq)))]
+        struct Config {
+            /// The verbosity of the logs
+            ///
+            /// The long description is not part of the help text
+            log_level: u8,
+            debug: bool,
+            name: Option<String>,
+            #[patch(skip_wrap)]
+            tags: Option<String>,
+            #[patch(nesting)]
+            tls: Tls,
+        }
+
+        #[derive(Debug, PartialEq, Patch)]
+        #[patch(cli, attribute(derive(Debug, PartialEq)))]
+        struct Tls {
+            enabled: bool,
+            cert_path: String,
+        }
+
+        let parse = |args: &[&str]| {
+            let command = <ConfigPatch as clap::Args>::augment_args(clap::Command::new("app"));
+            command
+                .try_get_matches_from(args)
+                .and_then(|matches| <ConfigPatch as clap::FromArgMatches>::from_arg_matches(&matches))
+        };
+
+        assert_eq!(
+            parse(&["app", "--log-level", "3"]).unwrap(),
+            ConfigPatch {
+                log_level: Some(3),
+                debug: None,
+                name: None,
+                tags: None,
+                tls: TlsPatch {
+                    enabled: None,
+                    cert_path: None,
+                },
+            }
+        );
+
+        let patch = parse(&[
+            "app",
+            "--debug",
+            "--name",
+            "svc",
+            "--tags",
+            "a,b",
+            "--tls-enabled",
+            "--tls-cert-path",
+            "/etc/cert.pem",
+        ])
+        .unwrap();
+        let mut config = Config {
+            log_level: 1,
+            debug: false,
+            name: None,
+            tags: None,
+            tls: Tls {
+                enabled: false,
+                cert_path: String::new(),
+            },
+        };
+        config.apply(patch);
+        assert_eq!(
+            config,
+            Config {
+                log_level: 1,
+                debug: true,
+                name: Some(String::from("svc")),
+                tags: Some(String::from("a,b")),
+                tls: Tls {
+                    enabled: true,
+                    cert_path: String::from("/etc/cert.pem"),
+                },
+            }
+        );
+
+        assert!(parse(&["app", "--log-level", "loud"]).is_err());
+        assert!(parse(&["app", "--debug=true"]).is_err());
+        let command = <ConfigPatch as clap::Args>::augment_args(clap::Command::new("app"));
+        let log_level = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "log-level")
+            .unwrap();
+        assert_eq!(
+            log_level.get_help().unwrap().to_string(),
+            "The verbosity of the logs"
+        );
+    }
+
+    #[cfg(all(feature = "schemars", feature = "nesting"))]
+    #[test]
+    fn test_schema() {
+        use alloc::vec::Vec;
+        use struct_patch::Filler;
+
+        #[derive(Patch)]
+        #[patch(attribute(derive(serde::Deserialize, schemars::JsonSchema)))]
+        #[patch(attribute(serde(rename_all = "camelCase")))]
+        struct Config {
+            /// Verbosity of the logs
+            log_level: u8,
+            #[patch(attribute(serde(rename = "threads")))]
+            worker_count: u8,
+            #[patch(attribute(serde(skip)))]
+            #[allow(dead_code)]
+            secret: String,
+            #[patch(empty_value = false)]
+            debug: bool,
+            name: Option<String>,
+            #[patch(skip_wrap)]
+            tags: Option<Vec<String>>,
+            #[patch(nesting)]
+            server: Server,
+        }
+
+        #[derive(Patch)]
+        #[patch(attribute(derive(serde::Deserialize, schemars::JsonSchema)))]
+        struct Server {
+            port: u16,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Filler)]
+        #[filler(attribute(derive(schemars::JsonSchema)))]
+        struct Defaults {
+            /// Fallback name
+            name: Option<String>,
+            tags: Vec<String>,
+            #[filler(empty_value = u16::MAX)]
+            port: u16,
+        }
+
+        insta::assert_json_snapshot!("patch_schema", schemars::schema_for!(ConfigPatch));
+        insta::assert_json_snapshot!("filler_schema", schemars::schema_for!(DefaultsFiller));
+    }
+
+    #[cfg(feature = "nesting")]
+    #[test]
+    fn test_filler_nesting() {
+        use alloc::vec;
+        use alloc::vec::Vec;
+        use struct_patch::Filler;
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Server {
+            host: Option<String>,
+            #[filler(empty_value = 0)]
+            port: u16,
+        }
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Config {
+            name: Option<String>,
+            #[filler(nesting)]
+            server: Server,
+            #[filler(nesting)]
+            backup: Option<Server>,
+            #[filler(nesting)]
+            proxy: Option<Server>,
+            tags: Vec<String>,
+        }
+
+        let mut config = Config {
+            server: Server {
+                host: Some(String::from("a")),
+                port: 0,
+            },
+            proxy: Some(Server {
+                host: None,
+                port: 3128,
+            }),
+            ..Default::default()
+        };
+        let filler = || ConfigFiller {
+            name: Some(String::from("default")),
+            server: ServerFiller {
+                host: Some(String::from("b")),
+                port: 80,
+            },
+            backup: Some(ServerFiller {
+                host: None,
+                port: 81,
+            }),
+            proxy: Some(ServerFiller {
+                host: Some(String::from("c")),
+                port: 8080,
+            }),
+            tags: vec![String::from("t")],
+        };
+        // `Filler::apply` is called explicitly, `Patch` is also in scope
+        Filler::apply(&mut config, filler());
+        assert_eq!(
+            config,
+            Config {
+                name: Some(String::from("default")),
+                server: Server {
+                    host: Some(String::from("a")),
+                    port: 80,
+                },
+                backup: Some(Server {
+                    host: None,
+                    port: 81,
+                }),
+                proxy: Some(Server {
+                    host: Some(String::from("c")),
+                    port: 3128,
+                }),
+                tags: vec![String::from("t")],
+            }
+        );
+
+        // an empty filler leaves `None` unfilled, an empty nested filler fills it with the default
+        let mut config = Config::default();
+        Filler::apply(&mut config, Config::new_empty_filler());
+        assert_eq!(config, Config::default());
+        let mut empty_backup = Config::new_empty_filler();
+        empty_backup.backup = Some(ServerFiller::default());
+        Filler::apply(&mut config, empty_backup);
+        assert_eq!(config.backup, Some(Server::default()));
+
+        #[cfg(feature = "status")]
+        {
+            use struct_patch::Status;
+            assert!(Config::new_empty_filler().is_empty());
+            let mut empty_backup = Config::new_empty_filler();
+            empty_backup.backup = Some(ServerFiller::default());
+            assert!(!empty_backup.is_empty());
+            let mut port = Config::new_empty_filler();
+            port.server.port = 1;
+            assert!(!port.is_empty());
+        }
+
+        #[cfg(feature = "op")]
+        {
+            let filler = Config::new_empty_filler() + filler();
+            assert_eq!(filler.server.port, 80);
+            let mut a = Config::new_empty_filler();
+            a.backup = Some(ServerFiller {
+                host: Some(String::from("d")),
+                port: 0,
+            });
+            let filler = a + filler;
+            let backup = filler.backup.unwrap();
+            assert_eq!((backup.host.as_deref(), backup.port), (Some("d"), 81));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_fillable() {
+        extern crate std;
+        use alloc::collections::BTreeSet;
+        use struct_patch::Filler;
+
+        type Tags = BTreeSet<&'static str>;
+
+        mod fixed {
+            /// A `Vec` which is not a collection of `std`
+            #[allow(dead_code)]
+            #[derive(Debug, Default, PartialEq)]
+            pub struct Vec<T>(pub [Option<T>; 4]);
+        }
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Item {
+            #[filler(fillable)]
+            name: alloc::string::String,
+            #[filler(addable)]
+            labels: std::collections::HashMap<&'static str, u8>,
+            #[filler(fillable, addable)]
+            tags: Tags,
+            // not filled
+            history: fixed::Vec<u8>,
+        }
+
+        let filler = || ItemFiller {
+            name: String::from("a"),
+            labels: [("x", 1)].into_iter().collect(),
+            tags: ["t"].into_iter().collect(),
+        };
+        let mut item = Item::default();
+        Filler::apply(&mut item, filler());
+        assert_eq!(item.name, "a");
+        assert_eq!(item.labels.get("x"), Some(&1));
+        assert!(item.tags.contains("t"));
+
+        // the fields are not empty, so they are not filled
+        Filler::apply(
+            &mut item,
+            ItemFiller {
+                name: String::from("b"),
+                labels: [("y", 2)].into_iter().collect(),
+                tags: ["u"].into_iter().collect(),
+            },
+        );
+        assert_eq!(item.name, "a");
+        assert_eq!(item.labels.len(), 1);
+        assert_eq!(item.tags.len(), 1);
+
+        #[cfg(feature = "status")]
+        {
+            use struct_patch::Status;
+            assert!(Item::new_empty_filler().is_empty());
+            assert!(!filler().is_empty());
+        }
+
+        #[cfg(feature = "op")]
+        {
+            let mut other = Item::new_empty_filler();
+            other.labels.insert("y", 2);
+            other.tags.insert("u");
+            let filler = filler() + other;
+            assert_eq!(filler.labels.len(), 2);
+            assert_eq!(filler.tags.len(), 2);
+            assert_eq!(filler.name, "a");
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_filler_merge_keys() {
+        extern crate std;
+        use alloc::collections::BTreeSet;
+        use std::collections::HashMap;
+        use struct_patch::Filler;
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Config {
+            #[filler(merge_keys)]
+            plugins: HashMap<&'static str, u8>,
+            #[filler(merge_members)]
+            features: BTreeSet<&'static str>,
+        }
+
+        let defaults = || ConfigFiller {
+            plugins: [("fmt", 1), ("lint", 1)].into_iter().collect(),
+            features: ["a", "b"].into_iter().collect(),
+        };
+        let mut config = Config {
+            plugins: [("lint", 2)].into_iter().collect(),
+            features: ["c"].into_iter().collect(),
+        };
+        Filler::apply(&mut config, defaults());
+        assert_eq!(
+            config,
+            Config {
+                plugins: [("fmt", 1), ("lint", 2)].into_iter().collect(),
+                features: ["a", "b", "c"].into_iter().collect(),
+            }
+        );
+
+        #[cfg(feature = "status")]
+        {
+            use struct_patch::Status;
+            assert!(Config::new_empty_filler().is_empty());
+            assert!(!defaults().is_empty());
+        }
+
+        #[cfg(feature = "op")]
+        {
+            let mut user = Config::new_empty_filler();
+            user.plugins.insert("fmt", 3);
+            let filler = user + defaults();
+            assert_eq!(filler.plugins.get("fmt"), Some(&3));
+            assert_eq!(filler.plugins.get("lint"), Some(&1));
+        }
+    }
+
+    #[cfg(all(feature = "nesting", feature = "alloc"))]
+    #[test]
+    fn test_unfilled() {
+        use alloc::vec::Vec;
+        use struct_patch::Filler;
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Server {
+            host: Option<String>,
+            #[filler(empty_value = 0)]
+            port: u16,
+        }
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Config {
+            name: Option<String>,
+            #[filler(nesting)]
+            server: Server,
+            #[filler(nesting)]
+            backup: Option<Server>,
+            verbose: bool,
+        }
+
+        let mut config = Config {
+            server: Server {
+                host: Some(String::from("a")),
+                port: 0,
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            config.unfilled_fields().collect::<Vec<_>>(),
+            ["name", "server", "backup"]
+        );
+
+        let error = config.ensure_filled().unwrap_err();
+        assert_eq!(error.fields, ["name", "server.port", "backup"]);
+
+        let filled = config.apply_report(ConfigFiller {
+            name: Some(String::from("b")),
+            server: ServerFiller {
+                host: Some(String::from("c")),
+                port: 80,
+            },
+            backup: Some(ServerFiller {
+                host: None,
+                port: 81,
+            }),
+        });
+        // the host of the server is already filled
+        assert_eq!(filled, ["name", "server.port", "backup"]);
+        assert_eq!(config.server.host.as_deref(), Some("a"));
+        assert_eq!(config.unfilled_fields().collect::<Vec<_>>(), ["backup"]);
+        assert_eq!(config.ensure_filled().unwrap_err().fields, ["backup.host"]);
+
+        let filled = config.apply_report(ConfigFiller {
+            backup: Some(ServerFiller {
+                host: Some(String::from("d")),
+                port: 0,
+            }),
+            ..Default::default()
+        });
+        assert_eq!(filled, ["backup.host"]);
+        assert_eq!(config.ensure_filled(), Ok(()));
+    }
+
+    #[test]
+    fn test_filler_container() {
+        use struct_patch::Filler;
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(name = "Defaults", attribute(derive(Debug, Default, PartialEq)))]
+        struct Config {
+            #[filler(attribute(allow(dead_code)))]
+            name: Option<String>,
+            #[filler(skip)]
+            id: Option<u32>,
+            #[filler(empty_value = 0)]
+            port: u16,
+        }
+
+        let defaults = || Defaults {
+            name: Some(String::from("a")),
+            port: 80,
+        };
+        let mut config = Config::default();
+        Filler::apply(&mut config, defaults());
+        assert_eq!(
+            config,
+            Config {
+                name: Some(String::from("a")),
+                id: None,
+                port: 80,
+            }
+        );
+
+        #[cfg(feature = "merge")]
+        {
+            use struct_patch::Merge;
+
+            // the non-empty values of the later filler win, like the values of the later patch
+            let user = Defaults {
+                port: 8080,
+                ..Default::default()
+            };
+            let merged = defaults().merge(user);
+            assert_eq!(
+                merged,
+                Defaults {
+                    name: Some(String::from("a")),
+                    port: 8080,
+                }
+            );
+            #[cfg(feature = "op")]
+            {
+                assert_eq!(defaults() << Defaults::default(), defaults());
+                assert_eq!(
+                    Defaults::default() << defaults() << Defaults {
+                        port: 8080,
+                        ..Default::default()
+                    },
+                    merged
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "nesting")]
+    #[test]
+    fn test_filler_retype() {
+        use struct_patch::Filler;
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(name = "LimitDefaults", attribute(derive(Debug, Default)))]
+        struct Limit {
+            max: Option<u32>,
+        }
+
+        #[derive(Debug, Default, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Config {
+            #[filler(name = "LimitDefaults")]
+            connections: Limit,
+            #[filler(name = "LimitDefaults")]
+            retries: Option<Limit>,
+        }
+
+        let mut config = Config::default();
+        Filler::apply(
+            &mut config,
+            ConfigFiller {
+                connections: LimitDefaults { max: Some(10) },
+                retries: Some(LimitDefaults { max: Some(3) }),
+            },
+        );
+        assert_eq!(config.connections.max, Some(10));
+        assert_eq!(config.retries, Some(Limit { max: Some(3) }));
+    }
+
+    #[cfg(feature = "nesting")]
+    #[test]
+    fn test_fill_from() {
+        use struct_patch::{Filler, HasFiller};
+
+        #[derive(Clone, Debug, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Server {
+            host: Option<String>,
+            #[filler(empty_value = 0)]
+            port: u16,
+        }
+
+        impl Default for Server {
+            fn default() -> Self {
+                Server {
+                    host: Some(String::from("localhost")),
+                    port: 80,
+                }
+            }
+        }
+
+        #[derive(Clone, Debug, Default, PartialEq, Filler)]
+        #[filler(attribute(derive(Debug, Default)))]
+        struct Config {
+            name: Option<String>,
+            #[filler(nesting)]
+            server: Server,
+            #[filler(nesting)]
+            backup: Option<Server>,
+        }
+
+        let config = || Config {
+            name: None,
+            server: Server {
+                host: None,
+                port: 8080,
+            },
+            backup: None,
+        };
+        let defaults = Config {
+            name: Some(String::from("app")),
+            backup: Some(Server::default()),
+            ..Default::default()
+        };
+        let expected = Config {
+            name: Some(String::from("app")),
+            server: Server {
+                host: Some(String::from("localhost")),
+                port: 8080,
+            },
+            backup: Some(Server::default()),
+        };
+
+        let mut filled = config();
+        filled.fill_from(&defaults);
+        assert_eq!(filled, expected);
+        assert_eq!(config().fill_from_owned(defaults), expected);
+
+        let filler = Config::defaults_filler();
+        assert_eq!(filler.name, None);
+        assert_eq!(filler.server.host.as_deref(), Some("localhost"));
+        assert_eq!(filler.server.port, 80);
+        assert!(filler.backup.is_none());
+    }
+
+    #[cfg(feature = "nesting")]
+    #[test]
+    fn test_filler_patch_conversion() {
+        use struct_patch::Filler;
+
+        #[derive(Debug, PartialEq, Patch, Filler)]
+        #[filler(patch)]
+        struct Server {
+            host: Option<String>,
+            #[filler(empty_value = 0)]
+            port: u16,
+        }
+
+        #[derive(Debug, PartialEq, Patch, Filler)]
+        #[filler(patch)]
+        struct Config {
+            name: Option<String>,
+            #[patch(skip_wrap)]
+            mode: Option<String>,
+            #[filler(skip)]
+            version: u32,
+            #[patch(nesting)]
+            #[filler(nesting)]
+            server: Server,
+        }
+
+        let patch = ConfigPatch {
+            name: Some(Some(String::from("app"))),
+            mode: None,
+            version: Some(2),
+            server: ServerPatch {
+                host: None,
+                port: Some(8080),
+            },
+        };
+        let filler = ConfigFiller::from(patch);
+        assert_eq!(filler.name.as_deref(), Some("app"));
+        assert_eq!(filler.mode, None);
+        assert_eq!(filler.server.host, None);
+        assert_eq!(filler.server.port, 8080);
+
+        let mut config = Config {
+            name: None,
+            mode: Some(String::from("fast")),
+            version: 1,
+            server: Server {
+                host: Some(String::from("localhost")),
+                port: 0,
+            },
+        };
+        Filler::apply(&mut config, filler);
+        assert_eq!(config.name.as_deref(), Some("app"));
+        assert_eq!(config.mode.as_deref(), Some("fast"));
+        assert_eq!(config.server.port, 8080);
+
+        let filler = ConfigFiller {
+            name: None,
+            mode: Some(String::from("slow")),
+            server: ServerFiller {
+                host: Some(String::from("example.com")),
+                port: 0,
+            },
+        };
+        let patch = filler.into_patch();
+        assert_eq!(patch.name, None);
+        assert_eq!(patch.mode.as_deref(), Some("slow"));
+        assert_eq!(patch.version, None);
+        assert_eq!(patch.server.host, Some(Some(String::from("example.com"))));
+        assert_eq!(patch.server.port, None);
+
+        Patch::apply(&mut config, patch);
+        assert_eq!(config.name.as_deref(), Some("app"));
+        assert_eq!(config.mode.as_deref(), Some("slow"));
+        assert_eq!(config.version, 1);
+        assert_eq!(config.server.host.as_deref(), Some("example.com"));
+        assert_eq!(config.server.port, 8080);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_patch_pointer() {
+        use alloc::borrow::Cow;
+        use alloc::boxed::Box;
+        use alloc::rc::Rc;
+        use alloc::sync::Arc;
+
+        #[derive(Clone, Debug, PartialEq, Patch)]
+        struct Config {
+            name: String,
+            port: u16,
+        }
+
+        let config = Config {
+            name: String::from("app"),
+            port: 80,
+        };
+        let patch = || struct_patch::patch!(Config { port: 8080 });
+        let patched = Config {
+            name: String::from("app"),
+            port: 8080,
+        };
+
+        let mut boxed = Box::new(config.clone());
+        Patch::apply(&mut boxed, patch());
+        assert_eq!(*boxed, patched);
+
+        // The shared value is cloned before it is patched
+        let mut shared = Rc::new(config.clone());
+        let other = Rc::clone(&shared);
+        Patch::apply(&mut shared, patch());
+        assert_eq!(*shared, patched);
+        assert_eq!(*other, config);
+
+        let mut shared = Arc::new(config.clone());
+        Patch::apply(&mut shared, patch());
+        assert_eq!(*shared, patched);
+        let patch_from_arc: ConfigPatch =
+            Arc::new(patched.clone()).into_patch_by_diff(Arc::new(config.clone()));
+        assert_eq!(patch_from_arc.port, Some(8080));
+        assert_eq!(patch_from_arc.name, None);
+
+        let mut borrowed = Cow::Borrowed(&config);
+        Patch::apply(&mut borrowed, patch());
+        assert!(matches!(borrowed, Cow::Owned(_)));
+        assert_eq!(*borrowed, patched);
+        assert_eq!(config.port, 80);
+    }
+
+    #[test]
+    fn test_has_patch() {
+        use struct_patch::{Filler, HasFiller, Patchable};
+
+        fn patched<T: Patchable>(mut value: T, patch: T::Patch) -> T {
+            value.apply(patch);
+            value
+        }
+
+        fn filled<T: HasFiller>(mut value: T, filler: T::Filler) -> T {
+            value.apply(filler);
+            value
+        }
+
+        #[derive(Clone, Debug, Default, PartialEq, Patch, Filler)]
+        #[patch(attribute(derive(Debug, PartialEq)))]
+        struct Item {
+            tag: Option<u8>,
+            #[filler(empty_value = 0)]
+            count: u32,
+        }
+
+        let item = Item {
+            tag: None,
+            count: 1,
+        };
+        let patch = struct_patch::empty::<Item>();
+        assert_eq!(
+            patch,
+            ItemPatch {
+                tag: None,
+                count: None
+            }
+        );
+        assert_eq!(patched(item.clone(), patch), item);
+        let patch = ItemPatch {
+            tag: None,
+            count: Some(2),
+        };
+        assert_eq!(patched(item.clone(), patch).count, 2);
+
+        let filler = ItemFiller {
+            tag: Some(3),
+            ..struct_patch::empty_filler::<Item>()
+        };
+        assert_eq!(filled(item.clone(), filler).tag, Some(3));
+
+        let current = Item {
+            tag: Some(3),
+            count: 1,
+        };
+        let patch = struct_patch::diff(&item, &current);
+        assert_eq!(
+            patch,
+            ItemPatch {
+                tag: Some(Some(3)),
+                count: None,
+            }
+        );
+        assert_eq!(struct_patch::diff_owned(item, current), patch);
+    }
 }
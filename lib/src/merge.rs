@@ -0,0 +1,230 @@
+#![cfg(feature = "merge")]
+//! Merge implementations for the containers and the primitives, so the renamed and `empty_value`
+//! fields of a patch can be merged
+//!
+//! The value of the later patch (`other`) wins for the values replaced as a whole, the maps are
+//! merged per key, and the lists are either replaced or concatenated, chosen by the [`Replace`]
+//! and [`Concat`] wrappers.
+use crate::Merge;
+use core::ops::{Deref, DerefMut};
+
+/// A value replaced as a whole by the later one when merged
+/// ```rust
+/// use struct_patch::{Merge, Replace};
+///
+/// let merged = Replace(vec![1, 2]).merge(Replace(vec![3]));
+/// assert_eq!(merged, Replace(vec![3]));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Replace<T>(pub T);
+
+impl<T> Merge for Replace<T> {
+    fn merge(self, other: Self) -> Self {
+        other
+    }
+}
+
+/// A collection extended by the items of the later one when merged
+/// ```rust
+/// use struct_patch::{Concat, Merge};
+///
+/// let merged = Concat(vec![1, 2]).merge(Concat(vec![3]));
+/// assert_eq!(merged, Concat(vec![1, 2, 3]));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Concat<T>(pub T);
+
+impl<T, I> Merge for Concat<T>
+where
+    T: Extend<I> + IntoIterator<Item = I>,
+{
+    fn merge(mut self, other: Self) -> Self {
+        self.0.extend(other.0);
+        self
+    }
+}
+
+macro_rules! wrapper_impls {
+    ($($wrapper:ident),*) => {
+        $(
+            impl<T> From<T> for $wrapper<T> {
+                fn from(value: T) -> Self {
+                    $wrapper(value)
+                }
+            }
+
+            impl<T> Deref for $wrapper<T> {
+                type Target = T;
+
+                fn deref(&self) -> &T {
+                    &self.0
+                }
+            }
+
+            impl<T> DerefMut for $wrapper<T> {
+                fn deref_mut(&mut self) -> &mut T {
+                    &mut self.0
+                }
+            }
+        )*
+    };
+}
+
+wrapper_impls!(Replace, Concat);
+
+macro_rules! replace_merge {
+    ($($ty:ty),*) => {
+        $(
+            impl Merge for $ty {
+                fn merge(self, other: Self) -> Self {
+                    other
+                }
+            }
+        )*
+    };
+}
+
+replace_merge!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    &'static str,
+    core::time::Duration
+);
+
+#[cfg(feature = "alloc")]
+mod merge_alloc {
+    use crate::Merge;
+    use alloc::boxed::Box;
+    use alloc::collections::BTreeMap;
+    use alloc::string::String;
+
+    replace_merge!(String);
+
+    impl<T: Merge> Merge for Box<T> {
+        fn merge(self, other: Self) -> Self {
+            Box::new((*self).merge(*other))
+        }
+    }
+
+    impl<K: Ord, V: Merge> Merge for BTreeMap<K, V> {
+        fn merge(mut self, other: Self) -> Self {
+            for (k, v) in other {
+                let v = match self.remove(&k) {
+                    Some(old) => old.merge(v),
+                    None => v,
+                };
+                self.insert(k, v);
+            }
+            self
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod merge_std {
+    extern crate std;
+
+    use crate::Merge;
+    use core::hash::{BuildHasher, Hash};
+    use std::collections::HashMap;
+
+    impl<K: Eq + Hash, V: Merge, S: BuildHasher> Merge for HashMap<K, V, S> {
+        fn merge(mut self, other: Self) -> Self {
+            for (k, v) in other {
+                let v = match self.remove(&k) {
+                    Some(old) => old.merge(v),
+                    None => v,
+                };
+                self.insert(k, v);
+            }
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as struct_patch;
+    use crate::Patch;
+
+    #[test]
+    fn test_merge_empty_value_fields() {
+        #[derive(Patch)]
+        #[patch(attribute(derive(Debug, PartialEq)))]
+        struct Item {
+            #[patch(empty_value = 0)]
+            count: u32,
+            #[patch(empty_value = false)]
+            enabled: bool,
+        }
+
+        let first = ItemPatch {
+            count: 1,
+            enabled: true,
+        };
+        let second = ItemPatch {
+            count: 2,
+            enabled: false,
+        };
+        assert_eq!(
+            first.merge(second),
+            ItemPatch {
+                count: 2,
+                enabled: true,
+            }
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_merge_alloc() {
+        use alloc::boxed::Box;
+        use alloc::collections::BTreeMap;
+        use alloc::vec;
+
+        let merged = Concat(vec![1]).merge(Concat(vec![2, 3]));
+        assert_eq!(*merged, vec![1, 2, 3]);
+        let merged = Replace(vec![1]).merge(Replace(vec![2, 3]));
+        assert_eq!(*merged, vec![2, 3]);
+        assert_eq!(Box::new(1).merge(Box::new(2)), Box::new(2));
+
+        let a = BTreeMap::from([("a", Concat(vec![1])), ("b", Concat(vec![2]))]);
+        let b = BTreeMap::from([("b", Concat(vec![3])), ("c", Concat(vec![4]))]);
+        let merged = a.merge(b);
+        assert_eq!(
+            merged,
+            BTreeMap::from([
+                ("a", Concat(vec![1])),
+                ("b", Concat(vec![2, 3])),
+                ("c", Concat(vec![4])),
+            ])
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_merge_hash_map() {
+        extern crate std;
+        use std::collections::HashMap;
+
+        let a = HashMap::from([("a", 1), ("b", 2)]);
+        let b = HashMap::from([("b", 3), ("c", 4)]);
+        assert_eq!(a.merge(b), HashMap::from([("a", 1), ("b", 3), ("c", 4)]));
+    }
+}
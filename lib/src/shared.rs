@@ -0,0 +1,232 @@
+#![cfg(all(feature = "std", feature = "field"))]
+//! A value shared between threads and updated by patches
+use crate::traits::{Fields, Patch, Patchable};
+
+extern crate std;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::vec::Vec;
+
+/// A value shared between threads, updated by applying patches
+///
+/// Readers take cheap [`snapshot`](Shared::snapshot)s, and every [`update`](Shared::update) patches
+/// the current value, copied first while snapshots of it are held, so a snapshot never sees a
+/// half-applied patch. The subscribers are notified of the fields whose values are changed.
+/// ```rust
+/// # use struct_patch::{Patch, Shared};
+/// #[derive(Clone, Patch)]
+/// struct Config {
+///     log_level: u8,
+///     workers: usize,
+/// }
+///
+/// let shared: Shared<Config> = Shared::new(Config { log_level: 0, workers: 1 });
+/// let log_level = shared.subscribe(&[ConfigField::LogLevel]);
+///
+/// shared.update(struct_patch::patch!(Config { workers: 4 }));
+/// shared.update(struct_patch::patch!(Config { log_level: 3 }));
+/// shared.update(struct_patch::patch!(Config { log_level: 3 }));
+///
+/// let notification = log_level.try_recv().unwrap();
+/// assert_eq!(notification.fields, [ConfigField::LogLevel]);
+/// assert_eq!(notification.snapshot.log_level, 3);
+/// assert!(log_level.try_recv().is_err());
+/// assert_eq!(shared.snapshot().workers, 4);
+/// ```
+pub struct Shared<T>
+where
+    T: Patchable,
+    T::Patch: Fields,
+{
+    value: RwLock<Arc<T>>,
+    subscribers: Mutex<Vec<Subscriber<T, <T::Patch as Fields>::Field>>>,
+}
+
+struct Subscriber<T, F> {
+    mask: Vec<F>,
+    sender: Sender<Notification<T, F>>,
+}
+
+/// The notification sent to the subscribers of a [`Shared`] value
+#[derive(Debug)]
+pub struct Notification<T, F> {
+    /// The subscribed fields changed by the patch, in declaration order
+    pub fields: Vec<F>,
+    /// The value right after the patch is applied
+    pub snapshot: Arc<T>,
+}
+
+impl<T, F: Clone> Clone for Notification<T, F> {
+    fn clone(&self) -> Self {
+        Notification {
+            fields: self.fields.clone(),
+            snapshot: self.snapshot.clone(),
+        }
+    }
+}
+
+impl<T> Shared<T>
+where
+    T: Patchable + Clone,
+    T::Patch: Fields,
+{
+    /// Wrap the value
+    pub fn new(value: T) -> Self {
+        Shared {
+            value: RwLock::new(Arc::new(value)),
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the current value
+    pub fn snapshot(&self) -> Arc<T> {
+        self.value
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Apply the patch, and notify the subscribers of the fields changed by the patch
+    ///
+    /// The fields set to their current values are not changed, so they are not notified.  It is
+    /// not named `apply`, which the method syntax on an `Arc<Shared<T>>` resolves to the `Patch`
+    /// impl of `Arc`.
+    pub fn update(&self, patch: T::Patch) {
+        let mut value = self.value.write().unwrap_or_else(PoisonError::into_inner);
+
+        // Notify while still holding the write lock, so the notifications keep the order of the
+        // patches applied from different threads
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let changed_fields: Vec<<T::Patch as Fields>::Field> = if subscribers.is_empty() {
+            Vec::new()
+        } else {
+            <T::Patch as Fields>::FIELDS
+                .iter()
+                .copied()
+                .filter(|f| value.is_changed_by(&patch, *f))
+                .collect()
+        };
+        Patch::apply(Arc::make_mut(&mut *value), patch);
+        if changed_fields.is_empty() {
+            return;
+        }
+
+        let snapshot = value.clone();
+        subscribers.retain(|subscriber| {
+            let fields: Vec<<T::Patch as Fields>::Field> = changed_fields
+                .iter()
+                .copied()
+                .filter(|f| subscriber.mask.contains(f))
+                .collect();
+            fields.is_empty()
+                || subscriber
+                    .sender
+                    .send(Notification {
+                        fields,
+                        snapshot: snapshot.clone(),
+                    })
+                    .is_ok()
+        });
+    }
+
+    /// Subscribe to the patches changing any of the fields, the subscription ends when the
+    /// receiver is dropped
+    pub fn subscribe(
+        &self,
+        fields: &[<T::Patch as Fields>::Field],
+    ) -> Receiver<Notification<T, <T::Patch as Fields>::Field>> {
+        let (sender, receiver) = channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Subscriber {
+                mask: fields.to_vec(),
+                sender,
+            });
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as struct_patch;
+    use crate::Patch;
+    use std::thread;
+
+    #[derive(Clone, Debug, PartialEq, Patch)]
+    #[patch(attribute(derive(Debug, Default)))]
+    struct Config {
+        log_level: u8,
+        workers: usize,
+        name: Option<&'static str>,
+    }
+
+    #[test]
+    fn test_shared() {
+        let shared: Arc<Shared<Config>> = Arc::new(Shared::new(Config {
+            log_level: 0,
+            workers: 0,
+            name: None,
+        }));
+        let before = shared.snapshot();
+        let workers = shared.subscribe(&[ConfigField::Workers, ConfigField::Name]);
+        let all = shared.subscribe(ConfigField::ALL);
+        let dropped = shared.subscribe(ConfigField::ALL);
+        drop(dropped);
+
+        let handles: Vec<_> = (1..=4)
+            .map(|n| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    shared.update(ConfigPatch {
+                        workers: Some(n),
+                        ..ConfigPatch::default()
+                    })
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        shared.update(ConfigPatch {
+            log_level: Some(3),
+            name: Some(Some("svc")),
+            ..ConfigPatch::default()
+        });
+
+        assert_eq!(before.workers, 0);
+        let after = shared.snapshot();
+        assert_eq!(after.log_level, 3);
+        assert_eq!(after.name, Some("svc"));
+
+        let notifications: Vec<_> = workers.try_iter().collect();
+        assert_eq!(notifications.len(), 5);
+        for notification in &notifications[..4] {
+            assert_eq!(notification.fields, [ConfigField::Workers]);
+            assert_eq!(notification.snapshot.log_level, 0);
+        }
+        assert_eq!(notifications[4].fields, [ConfigField::Name]);
+        assert_eq!(*notifications[4].snapshot, *after);
+
+        let last = all.try_iter().last().unwrap();
+        assert_eq!(last.fields, [ConfigField::LogLevel, ConfigField::Name]);
+
+        // empty patches are applied without notifying anyone
+        shared.update(ConfigPatch::default());
+        assert!(workers.try_recv().is_err());
+
+        // the fields set to their current values are not changed
+        shared.update(ConfigPatch {
+            log_level: Some(3),
+            name: Some(Some("svc")),
+            workers: Some(8),
+        });
+        assert_eq!(all.try_recv().unwrap().fields, [ConfigField::Workers]);
+        assert!(all.try_recv().is_err());
+        assert_eq!(shared.subscribers.lock().unwrap().len(), 2);
+    }
+}
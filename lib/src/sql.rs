@@ -0,0 +1,330 @@
+#![cfg(feature = "sql")]
+//! Render patches as the `SET` clause of a SQL `UPDATE` statement
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The SQL dialect, which decides the placeholders and how the column names are quoted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    /// `"column" = $1`
+    Postgres,
+    /// `"column" = ?1`
+    Sqlite,
+    /// `` `column` = ? ``
+    MySql,
+}
+
+/// A value bound to a placeholder of the `SET` clause
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// `NULL`, the value of a cleared `Option` field
+    Null,
+    /// A boolean, stored as an integer by the dialects without a boolean type
+    Bool(bool),
+    /// A signed integer, the smaller integers are widened
+    Integer(i64),
+    /// A floating point number
+    Real(f64),
+    /// A string
+    Text(String),
+    /// The bytes of a binary column
+    Blob(Vec<u8>),
+}
+
+/// Convert a field value into a SQL [`Value`]
+///
+/// The unsigned integers which may not fit in an `i64` are not implemented, implement it on a
+/// newtype to pick the conversion.
+pub trait ToSqlValue {
+    /// The value bound to the placeholder of the column
+    fn to_sql_value(&self) -> Value;
+}
+
+macro_rules! impl_to_sql_value {
+    ($variant:ident($as:ty): $($ty:ty),*) => {
+        $(
+            impl ToSqlValue for $ty {
+                fn to_sql_value(&self) -> Value {
+                    Value::$variant(<$as>::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+impl_to_sql_value!(Bool(bool): bool);
+impl_to_sql_value!(Integer(i64): i8, i16, i32, i64, u8, u16, u32);
+impl_to_sql_value!(Real(f64): f32, f64);
+
+impl ToSqlValue for str {
+    fn to_sql_value(&self) -> Value {
+        Value::Text(String::from(self))
+    }
+}
+
+impl ToSqlValue for String {
+    fn to_sql_value(&self) -> Value {
+        Value::Text(self.clone())
+    }
+}
+
+impl ToSqlValue for Cow<'_, str> {
+    fn to_sql_value(&self) -> Value {
+        Value::Text(String::from(self.as_ref()))
+    }
+}
+
+impl ToSqlValue for [u8] {
+    fn to_sql_value(&self) -> Value {
+        Value::Blob(self.to_vec())
+    }
+}
+
+impl ToSqlValue for Vec<u8> {
+    fn to_sql_value(&self) -> Value {
+        Value::Blob(self.clone())
+    }
+}
+
+impl<T: ToSqlValue> ToSqlValue for Option<T> {
+    fn to_sql_value(&self) -> Value {
+        match self {
+            Some(v) => v.to_sql_value(),
+            None => Value::Null,
+        }
+    }
+}
+
+impl<T: ToSqlValue + ?Sized> ToSqlValue for &T {
+    fn to_sql_value(&self) -> Value {
+        (**self).to_sql_value()
+    }
+}
+
+/// The `SET` clause under construction, with one placeholder per assignment
+#[derive(Clone, Debug)]
+pub struct SetClause {
+    dialect: Dialect,
+    sql: String,
+    values: Vec<Value>,
+}
+
+impl SetClause {
+    /// An empty `SET` clause with the placeholders and the quotes of the dialect
+    pub fn new(dialect: Dialect) -> Self {
+        SetClause {
+            dialect,
+            sql: String::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Add the assignment of the value to the column, the column is the concatenation of the
+    /// prefix and the name
+    pub fn push(&mut self, prefix: &str, name: &str, value: Value) {
+        if !self.values.is_empty() {
+            self.sql.push_str(", ");
+        }
+        let quote = match self.dialect {
+            Dialect::Postgres | Dialect::Sqlite => '"',
+            Dialect::MySql => '`',
+        };
+        self.sql.push(quote);
+        for c in prefix.chars().chain(name.chars()) {
+            if c == quote {
+                self.sql.push(quote);
+            }
+            self.sql.push(c);
+        }
+        self.sql.push(quote);
+        self.values.push(value);
+        let index = self.values.len();
+        match self.dialect {
+            Dialect::Postgres => self.sql.push_str(&alloc::format!(" = ${}", index)),
+            Dialect::Sqlite => self.sql.push_str(&alloc::format!(" = ?{}", index)),
+            Dialect::MySql => self.sql.push_str(" = ?"),
+        }
+    }
+
+    /// Returns `true` if nothing is assigned
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The `SET` clause without the `SET` keyword, and the values of the placeholders in order
+    pub fn finish(self) -> (String, Vec<Value>) {
+        (self.sql, self.values)
+    }
+}
+
+/// Render a patch as the `SET` clause of a SQL `UPDATE` statement, implemented by the patches of
+/// the structs with the `#[patch(sql)]` attribute
+///
+/// The column of a field is its `#[patch(column = "...")]` name or the field name, and the columns
+/// of a `#[patch(nesting)]` field are prefixed with its column and `_`.
+/// ```rust
+/// # use struct_patch::Patch;
+/// use struct_patch::sql::{Dialect, ToSqlSet, Value};
+///
+/// #[derive(Patch)]
+/// #[patch(sql)]
+/// struct User {
+///     #[patch(column = "user_name")]
+///     name: String,
+///     age: u32,
+///     email: Option<String>,
+/// }
+///
+/// let patch = struct_patch::patch!(User { name: String::from("Ann"), email: None });
+/// let (set, values) = patch.to_sql_set(Dialect::Postgres);
+/// assert_eq!(set, r#""user_name" = $1, "email" = $2"#);
+/// assert_eq!(values, [Value::Text(String::from("Ann")), Value::Null]);
+/// ```
+pub trait ToSqlSet {
+    /// Add the assignments of the fields set in the patch, with the columns prefixed
+    fn push_sql_set(&self, prefix: &str, set: &mut SetClause);
+
+    /// The `SET` clause without the `SET` keyword, and the values of the placeholders in order,
+    /// the clause is empty if no field is set
+    fn to_sql_set(&self, dialect: Dialect) -> (String, Vec<Value>) {
+        let mut set = SetClause::new(dialect);
+        self.push_sql_set("", &mut set);
+        set.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as struct_patch;
+    use crate::Patch;
+    extern crate std;
+    use std::format;
+
+    #[derive(Debug, PartialEq, Patch)]
+    #[patch(sql, attribute(derive(Debug, Default, PartialEq)))]
+    struct Address {
+        city: String,
+        #[patch(column = "zip_code")]
+        zip: Option<u32>,
+    }
+
+    #[cfg(feature = "nesting")]
+    #[derive(Debug, PartialEq, Patch)]
+    #[patch(sql, attribute(derive(Default)))]
+    struct User {
+        id: i64,
+        name: String,
+        active: bool,
+        score: f64,
+        avatar: Option<Vec<u8>>,
+        #[patch(nesting, column = "home")]
+        address: Address,
+    }
+
+    #[cfg(feature = "nesting")]
+    fn sqlite_value(value: Value) -> rusqlite::types::Value {
+        match value {
+            Value::Null => rusqlite::types::Value::Null,
+            Value::Bool(v) => rusqlite::types::Value::Integer(v.into()),
+            Value::Integer(v) => rusqlite::types::Value::Integer(v),
+            Value::Real(v) => rusqlite::types::Value::Real(v),
+            Value::Text(v) => rusqlite::types::Value::Text(v),
+            Value::Blob(v) => rusqlite::types::Value::Blob(v),
+        }
+    }
+
+    #[test]
+    fn test_sql_set() {
+        let patch = AddressPatch {
+            city: Some(String::from("Taipei")),
+            zip: Some(None),
+        };
+        assert_eq!(
+            patch.to_sql_set(Dialect::Postgres),
+            (
+                String::from(r#""city" = $1, "zip_code" = $2"#),
+                Vec::from([Value::Text(String::from("Taipei")), Value::Null])
+            )
+        );
+        assert_eq!(
+            patch.to_sql_set(Dialect::MySql).0,
+            "`city` = ?, `zip_code` = ?"
+        );
+        assert_eq!(
+            AddressPatch::default().to_sql_set(Dialect::Sqlite),
+            (String::new(), Vec::new())
+        );
+
+        let mut set = SetClause::new(Dialect::Sqlite);
+        set.push("", r#"a"b"#, Value::Bool(true));
+        assert_eq!(set.finish().0, r#""a""b" = ?1"#);
+    }
+
+    #[cfg(feature = "nesting")]
+    #[test]
+    fn test_sql_set_sqlite() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE user (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                active INTEGER NOT NULL,
+                score REAL NOT NULL,
+                avatar BLOB,
+                home_city TEXT NOT NULL,
+                home_zip_code INTEGER
+            );
+            INSERT INTO user VALUES (1, 'a', 0, 0.5, x'00', 'Taipei', 100);",
+        )
+        .unwrap();
+
+        let patch = UserPatch {
+            name: Some(String::from("b")),
+            score: Some(1.5),
+            avatar: Some(None),
+            address: AddressPatch {
+                city: Some(String::from("Tainan")),
+                zip: Some(None),
+            },
+            ..Default::default()
+        };
+        let (set, values) = patch.to_sql_set(Dialect::Sqlite);
+        let mut params: Vec<_> = values.into_iter().map(sqlite_value).collect();
+        params.push(rusqlite::types::Value::Integer(1));
+        let sql = format!("UPDATE user SET {} WHERE id = ?{}", set, params.len());
+        let updated = conn
+            .execute(&sql, rusqlite::params_from_iter(params))
+            .unwrap();
+        assert_eq!(updated, 1);
+
+        let row = conn
+            .query_row(
+                "SELECT name, active, score, avatar, home_city, home_zip_code FROM user",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, bool>(1)?,
+                        row.get::<_, f64>(2)?,
+                        row.get::<_, Option<Vec<u8>>>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, Option<u32>>(5)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            row,
+            (
+                String::from("b"),
+                false,
+                1.5,
+                None,
+                String::from("Tainan"),
+                None
+            )
+        );
+    }
+}
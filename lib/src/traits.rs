@@ -60,6 +60,56 @@
 /// // struct ItemOverlay {}
 /// ```
 ///
+/// ### `#[patch(after_apply = ...)]`
+/// Call the function with the patched struct at the end of every `apply`, to recompute the fields
+/// derived from other fields
+/// ```rust
+/// # use struct_patch::Patch;
+/// fn recompute(item: &mut Item) {
+///     item.name_len = item.name.len();
+/// }
+///
+/// #[derive(Patch)]
+/// #[patch(after_apply = recompute)]
+/// struct Item {
+///     name: String,
+///     #[patch(skip)]
+///     name_len: usize,
+/// }
+///
+/// let mut item = Item { name: String::new(), name_len: 0 };
+/// item.apply(ItemPatch { name: Some("patched".into()) });
+/// assert_eq!(item.name_len, 7);
+/// ```
+///
+/// ### `#[patch(cli)]`
+/// With the `clap` feature, implement `clap::Args` and `clap::FromArgMatches` on the patch struct.
+/// Each field becomes a `--kebab-name` option with the first paragraph of its doc comment as the
+/// help text, `bool` fields become flags, and the options of `nesting` fields are prefixed with
+/// the field name.
+/// ```rust
+/// # #[cfg(feature = "clap")]
+/// # {
+/// # use struct_patch::Patch;
+/// #[derive(Patch)]
+/// #[patch(cli)]
+/// struct Config {
+///     /// Verbosity of the logs
+///     log_level: u8,
+///     debug: bool,
+/// }
+///
+/// // Generated options
+/// //     --log-level <LOG_LEVEL>  Verbosity of the logs
+/// //     --debug
+/// # }
+/// ```
+///
+/// ### `#[patch(audit)]`
+/// With the `audit` feature, generate `apply_audited`, which applies the patch and returns the
+/// records of the changed fields with their values before and after, see
+/// `struct_patch::audit::Audited`.
+///
 /// ## Field attributes
 /// ### `#[patch(skip)]`
 /// If you want certain fields to be unpatchable, you can let the derive macro skip certain fields when creating the patch struct
@@ -108,6 +158,188 @@
 /// item.apply(ItemPatch { tags: Some(vec![]) });
 /// assert_eq!(item.tags, Some(vec![]));
 /// ```
+///
+/// ### `#[patch(on_change = ...)]`
+/// Call the function with the old and new values after the field is actually changed by a patch.
+/// The field is compared with `PartialEq`, and the attribute cannot be used on `nesting` or
+/// renamed fields.
+/// ```rust
+/// # use struct_patch::Patch;
+/// fn on_log_level_change(old: &u8, new: &u8) {
+///     assert_ne!(old, new);
+/// }
+///
+/// #[derive(Patch)]
+/// struct Item {
+///     #[patch(on_change = on_log_level_change)]
+///     log_level: u8,
+/// }
+///
+/// let mut item = Item { log_level: 0 };
+/// item.apply(ItemPatch { log_level: Some(0) }); // not changed, the hook is not called
+/// item.apply(ItemPatch { log_level: Some(3) });
+/// ```
+///
+/// ### `#[patch(readonly)]`
+/// Keep the field in the patch struct, so it is accepted when deserializing, but reject the patch
+/// setting it.  `try_apply` returns a [`ReadOnly`](crate::error::ReadOnly) error naming the field
+/// instead of applying such a patch, `check_readonly` validates a patch without applying it, and
+/// `apply` leaves the field unchanged.  Both methods are only generated for the structs with
+/// read-only fields, or with `nesting` fields whose structs may have some.
+/// ```rust
+/// # use struct_patch::Patch;
+/// # use struct_patch::error::ReadOnly;
+/// #[derive(Patch)]
+/// struct Item {
+///     #[patch(readonly)]
+///     id: u32,
+///     data: u32,
+/// }
+///
+/// let mut item = Item { id: 1, data: 2 };
+/// let patch = ItemPatch { id: Some(10), data: Some(3) };
+/// assert_eq!(item.try_apply(patch), Err(ReadOnly { field: "id" }));
+/// assert_eq!(item.data, 2);
+/// ```
+///
+/// ### `#[patch(group = "...")]`
+/// Only let the callers allowed to change the group change the field with `apply_authorized`.
+/// The fields of a `nesting` field need the group of the nesting field as well, and the fields
+/// without a group can be changed by anyone.  A patch setting a field the caller is not allowed to
+/// change is rejected with a [`Forbidden`](crate::error::Forbidden) error, and `strip_unauthorized`
+/// removes such fields from the patch instead.  Both methods are only generated for the structs
+/// with groups, or with `nesting` fields whose structs may have some.
+/// ```rust
+/// # use struct_patch::Patch;
+/// # use struct_patch::error::Forbidden;
+/// #[derive(Patch)]
+/// struct Account {
+///     name: String,
+///     #[patch(group = "admin")]
+///     quota: u32,
+/// }
+///
+/// let mut account = Account { name: "a".into(), quota: 1 };
+/// let patch = || AccountPatch { name: Some("b".into()), quota: Some(2) };
+/// assert_eq!(
+///     account.apply_authorized(patch(), &["user"]),
+///     Err(Forbidden { field: "quota", group: "admin" })
+/// );
+/// let mut patch = patch();
+/// patch.strip_unauthorized(&["user"]);
+/// account.apply_authorized(patch, &["user"]).unwrap();
+/// assert_eq!((account.name.as_str(), account.quota), ("b", 1));
+/// ```
+///
+/// ### `#[patch(redact)]`
+/// Print the field as `Some(<redacted>)` when it is set.  When the patch struct derives `Debug`
+/// with `#[patch(attribute(derive(Debug)))]`, the derive is replaced by an impl hiding the value,
+/// and the change enum of the `change` feature redacts the field as well.
+/// ```rust
+/// # use struct_patch::Patch;
+/// #[derive(Patch)]
+/// #[patch(attribute(derive(Debug)))]
+/// struct Item {
+///     user: String,
+///     #[patch(redact)]
+///     password: String,
+/// }
+///
+/// let patch = ItemPatch {
+///     user: Some("admin".into()),
+///     password: Some("hunter2".into()),
+/// };
+/// assert_eq!(
+///     format!("{:?}", patch),
+///     r#"ItemPatch { user: Some("admin"), password: Some(<redacted>) }"#
+/// );
+/// ```
+///
+/// ### `#[patch(column = "...")]`
+/// With the `sql` feature, rename the column of the field in the `SET` clause rendered by the
+/// `struct_patch::sql::ToSqlSet` impl of `#[patch(sql)]`.  On a `nesting` field, it is the prefix
+/// of the columns of the nested fields, whose struct needs `#[patch(sql)]` as well.
+///
+/// ## Field enum
+/// With the `field` feature, the derive also generates an enum named after the struct with one
+/// variant per patchable field, and helpers on the patch struct to select fields with it.
+/// ```rust
+/// # #[cfg(feature = "field")]
+/// # {
+/// # use struct_patch::Patch;
+/// #[derive(Patch)]
+/// struct Item {
+///     field_bool: bool,
+///     field_int: usize,
+/// }
+///
+/// // Generated enum
+/// // enum ItemField {
+/// //     FieldBool,
+/// //     FieldInt,
+/// // }
+///
+/// let mut patch = ItemPatch {
+///     field_bool: Some(true),
+///     field_int: Some(7),
+/// };
+/// patch.retain(|f| f != ItemField::FieldBool);
+/// assert!(patch.fields().eq([ItemField::FieldInt]));
+/// # }
+/// ```
+///
+/// ## Change enum
+/// With the `change` feature, the derive also generates an enum with one variant per patchable
+/// field carrying its new value, so a patch can be consumed as a stream of typed changes.  The enum
+/// implements `Clone` and `Debug` when they are derived for the patch struct, and
+/// `apply_with_events` passes each change to the sink by reference before applying it.
+/// ```rust
+/// # #[cfg(feature = "change")]
+/// # {
+/// # use struct_patch::Patch;
+/// #[derive(Default, Patch)]
+/// struct Item {
+///     field_bool: bool,
+///     field_int: usize,
+/// }
+///
+/// // Generated enum
+/// // enum ItemChange {
+/// //     FieldBool(bool),
+/// //     FieldInt(usize),
+/// // }
+///
+/// let mut item = Item::default();
+/// let patch = struct_patch::patch!(Item { field_int: 7 });
+/// item.apply_with_events(patch, |change| match change {
+///     ItemChange::FieldInt(v) => assert_eq!(*v, 7),
+///     ItemChange::FieldBool(_) => unreachable!(),
+/// });
+/// assert_eq!(item.field_int, 7);
+/// # }
+/// ```
+///
+/// ## Pointers
+/// With the `alloc` feature, the derive also implements the trait for `Box`, `Rc`, `Arc` and
+/// `Cow` of the struct, patching the struct in place.  The `Rc` and `Arc` impls are generic over
+/// the pointed type, so `apply` called on an `Arc<Wrapper>` with the method syntax resolves to
+/// them before an inherent `apply` method of `Wrapper`, which is then called with the path syntax.
+/// ```rust
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// # use struct_patch::Patch;
+/// # use std::sync::Arc;
+/// #[derive(Clone, Patch)]
+/// struct Item {
+///     field_int: usize,
+/// }
+///
+/// let mut shared = Arc::new(Item { field_int: 0 });
+/// let other = shared.clone();
+/// shared.apply(struct_patch::patch!(Item { field_int: 7 }));
+/// assert_eq!((shared.field_int, other.field_int), (7, 0));
+/// # }
+/// ```
 pub trait Patch<P> {
     /// Apply a patch
     fn apply(&mut self, patch: P);
@@ -130,6 +362,255 @@ pub trait Filler<F> {
     fn new_empty_filler() -> F;
 }
 
+/// A struct naming its patch type, so generic code can write `T::Patch`
+///
+/// Deriving [`Patch`] implements it with the generated patch struct.
+/// ```rust
+/// use struct_patch::{Patch, Patchable};
+/// use serde::Deserialize;
+///
+/// fn reload<T: Patchable>(t: &mut T, json: &str) -> serde_json::Result<()>
+/// where
+///     T::Patch: for<'de> Deserialize<'de>,
+/// {
+///     t.apply(serde_json::from_str(json)?);
+///     Ok(())
+/// }
+///
+/// #[derive(Default, Patch)]
+/// #[patch(attribute(derive(Deserialize)))]
+/// struct Item {
+///     field_int: usize,
+/// }
+///
+/// let mut item = Item::default();
+/// reload(&mut item, r#"{ "field_int": 7 }"#).unwrap();
+/// assert_eq!(item.field_int, 7);
+/// ```
+pub trait Patchable: Patch<<Self as Patchable>::Patch> + Sized {
+    /// The patch struct of the struct
+    type Patch;
+
+    /// Returns `true` if the field is set in the patch to a value other than its value in `self`
+    ///
+    /// The derive compares the values of the fields, and of the fields of the nesting fields.  The
+    /// fields patched by their own patch type with `#[patch(name = "...")]` are changed when they
+    /// are set, as this default does.
+    #[cfg(feature = "field")]
+    fn is_changed_by(&self, patch: &Self::Patch, field: <Self::Patch as Fields>::Field) -> bool
+    where
+        Self::Patch: Fields,
+    {
+        patch.is_set(field)
+    }
+
+    /// Returns an error naming the first `#[patch(readonly)]` field set in the patch, the derive
+    /// overrides it for the structs with readonly or nesting fields
+    #[doc(hidden)]
+    fn __struct_patch_check_readonly(_patch: &Self::Patch) -> Result<(), crate::error::ReadOnly> {
+        Ok(())
+    }
+
+    /// Strip or reject the fields of the groups which are not allowed from the patch, the derive
+    /// overrides it for the structs with grouped or nesting fields
+    #[doc(hidden)]
+    fn __struct_patch_authorize(
+        _patch: &mut Self::Patch,
+        _allowed: &[&str],
+        _denied: Option<&'static str>,
+        _strip: bool,
+    ) -> Result<(), crate::error::Forbidden> {
+        Ok(())
+    }
+}
+
+/// A struct naming its filler type, so generic code can write `T::Filler`, the [`Filler`]
+/// counterpart of [`Patchable`]
+///
+/// Deriving [`Filler`] implements it with the generated filler struct, and it fills a struct from
+/// a defaults instance of the same type.  It is not named `Fillable`, which is the trait of the
+/// field types filled when they are empty.
+/// ```rust
+/// use struct_patch::{Filler, HasFiller};
+///
+/// #[derive(Clone, Filler)]
+/// struct Server {
+///     host: Option<String>,
+///     #[filler(empty_value = 0)]
+///     port: u16,
+/// }
+///
+/// let defaults = Server { host: Some("localhost".into()), port: 80 };
+/// let mut server = Server { host: None, port: 8080 };
+/// server.fill_from(&defaults);
+/// assert_eq!((server.host.as_deref(), server.port), (Some("localhost"), 8080));
+/// ```
+pub trait HasFiller: Filler<<Self as HasFiller>::Filler> + Sized {
+    /// The filler struct of the struct
+    type Filler;
+
+    /// The filler with the values of the struct
+    fn into_filler(self) -> Self::Filler;
+
+    /// Fill the empty fields with the values of the defaults, which is cloned
+    fn fill_from(&mut self, defaults: &Self)
+    where
+        Self: Clone,
+    {
+        Filler::apply(self, defaults.clone().into_filler());
+    }
+
+    /// Fill the empty fields with the values of the defaults
+    fn fill_from_owned(mut self, defaults: Self) -> Self {
+        Filler::apply(&mut self, defaults.into_filler());
+        self
+    }
+
+    /// The filler with the values of `Default::default()`
+    fn defaults_filler() -> Self::Filler
+    where
+        Self: Default,
+    {
+        Self::default().into_filler()
+    }
+}
+
+/// A field type which `Filler` fills when it is empty
+///
+/// The fields opt in with `#[filler(fillable)]`, so the type aliases, `String` and the custom types
+/// are filled without guessing from the type name.  The impls for the `alloc` types need the
+/// `alloc` feature, and the ones for `HashMap` and `HashSet` need the `std` feature.
+/// ```rust
+/// # use struct_patch::{Fillable, Filler};
+/// #[derive(Debug, Default, PartialEq)]
+/// struct Port(u16);
+///
+/// impl Fillable for Port {
+///     fn is_empty(&self) -> bool {
+///         self.0 == 0
+///     }
+///
+///     fn empty() -> Self {
+///         Port(0)
+///     }
+///
+///     fn append(&mut self, other: Self) {
+///         self.0 += other.0;
+///     }
+/// }
+///
+/// #[derive(Filler)]
+/// struct Server {
+///     #[filler(fillable)]
+///     port: Port,
+/// }
+///
+/// let mut server = Server { port: Port(0) };
+/// server.apply(ServerFiller { port: Port(80) });
+/// assert_eq!(server.port, Port(80));
+/// server.apply(ServerFiller { port: Port(8080) });
+/// assert_eq!(server.port, Port(80));
+/// ```
+pub trait Fillable {
+    /// Returns `true` if the value is empty, and is replaced by the value of the filler
+    fn is_empty(&self) -> bool;
+
+    /// The empty value, used in the empty filler
+    fn empty() -> Self;
+
+    /// Add the value of the other filler, used by `+` on the fillers for the
+    /// `#[filler(addable)]` fields
+    fn append(&mut self, other: Self);
+}
+
+#[cfg(feature = "alloc")]
+mod fillable_alloc {
+    use super::Fillable;
+    use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    macro_rules! impl_fillable {
+        ($(<$($g:ident $(: $bound:path)?),*> $ty:ty),* $(,)?) => {
+            $(
+                impl<$($g $(: $bound)?),*> Fillable for $ty {
+                    fn is_empty(&self) -> bool {
+                        <$ty>::is_empty(self)
+                    }
+
+                    fn empty() -> Self {
+                        <$ty>::new()
+                    }
+
+                    fn append(&mut self, other: Self) {
+                        self.extend(other);
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_fillable!(
+        <T> Vec<T>,
+        <T> VecDeque<T>,
+        <T> LinkedList<T>,
+        <K: Ord, V> BTreeMap<K, V>,
+        <T: Ord> BTreeSet<T>,
+        <T: Ord> BinaryHeap<T>,
+    );
+
+    impl Fillable for String {
+        fn is_empty(&self) -> bool {
+            String::is_empty(self)
+        }
+
+        fn empty() -> Self {
+            String::new()
+        }
+
+        fn append(&mut self, other: Self) {
+            self.push_str(&other);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod fillable_std {
+    extern crate std;
+
+    use super::Fillable;
+    use core::hash::{BuildHasher, Hash};
+    use std::collections::{HashMap, HashSet};
+
+    impl<K: Eq + Hash, V, S: BuildHasher + Default> Fillable for HashMap<K, V, S> {
+        fn is_empty(&self) -> bool {
+            HashMap::is_empty(self)
+        }
+
+        fn empty() -> Self {
+            HashMap::default()
+        }
+
+        fn append(&mut self, other: Self) {
+            self.extend(other);
+        }
+    }
+
+    impl<T: Eq + Hash, S: BuildHasher + Default> Fillable for HashSet<T, S> {
+        fn is_empty(&self) -> bool {
+            HashSet::is_empty(self)
+        }
+
+        fn empty() -> Self {
+            HashSet::default()
+        }
+
+        fn append(&mut self, other: Self) {
+            self.extend(other);
+        }
+    }
+}
+
 #[cfg(feature = "status")]
 /// A patch struct with extra status information
 pub trait Status {
@@ -137,6 +618,19 @@ pub trait Status {
     fn is_empty(&self) -> bool;
 }
 
+#[cfg(feature = "field")]
+/// A patch struct with the field enum generated by the `field` feature
+pub trait Fields {
+    /// The field enum
+    type Field: Copy + Eq + 'static;
+
+    /// All the fields in declaration order
+    const FIELDS: &'static [Self::Field];
+
+    /// Returns `true` if the field is set in the patch
+    fn is_set(&self, field: Self::Field) -> bool;
+}
+
 #[cfg(feature = "merge")]
 /// A patch struct that can be merged to another one
 pub trait Merge {
@@ -0,0 +1,331 @@
+#![cfg(feature = "watch")]
+//! Reload patches from files on disk
+use crate::traits::{Fields, Patch};
+
+extern crate std;
+use core::fmt;
+use core::time::Duration;
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use std::vec::Vec;
+use std::{fs, io, thread};
+
+/// The error of loading a patch from a file
+#[derive(Debug)]
+pub enum Error {
+    /// The file can not be read
+    Io(io::Error),
+    /// The JSON file can not be deserialized into the patch
+    Json(serde_json::Error),
+    /// The TOML file is not valid UTF-8
+    Utf8(core::str::Utf8Error),
+    /// The TOML file can not be deserialized into the patch
+    Toml(toml::de::Error),
+    /// The file extension is neither `json` nor `toml`
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "can not read the patch file: {}", e),
+            Error::Json(e) => write!(f, "invalid JSON patch: {}", e),
+            Error::Utf8(e) => write!(f, "the TOML patch is not UTF-8: {}", e),
+            Error::Toml(e) => write!(f, "invalid TOML patch: {}", e),
+            Error::UnknownFormat(path) => write!(
+                f,
+                "{} is neither a `.json` nor a `.toml` file",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Utf8(e) => Some(e),
+            Error::Toml(e) => Some(e),
+            Error::UnknownFormat(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Json,
+    Toml,
+}
+
+/// A patch loaded from a JSON or TOML file, picked by the file extension
+#[derive(Debug)]
+pub struct FileSource<P> {
+    path: PathBuf,
+    format: Format,
+    content: Vec<u8>,
+    patch: P,
+}
+
+impl<P: DeserializeOwned> FileSource<P> {
+    /// Load the patch from the file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let format = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Format::Json,
+            Some("toml") => Format::Toml,
+            _ => return Err(Error::UnknownFormat(path)),
+        };
+        let content = fs::read(&path).map_err(Error::Io)?;
+        let patch = parse(format, &content)?;
+        Ok(FileSource {
+            path,
+            format,
+            content,
+            patch,
+        })
+    }
+
+    /// Read the file again, and returns `true` if the content is changed and the patch is
+    /// reloaded.  The previous patch is kept when the file can not be loaded.
+    pub fn reload(&mut self) -> Result<bool, Error> {
+        let content = fs::read(&self.path).map_err(Error::Io)?;
+        if content == self.content {
+            return Ok(false);
+        }
+        self.patch = parse(self.format, &content)?;
+        self.content = content;
+        Ok(true)
+    }
+}
+
+impl<P> FileSource<P> {
+    /// The path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The patch loaded from the file
+    pub fn patch(&self) -> &P {
+        &self.patch
+    }
+}
+
+fn parse<P: DeserializeOwned>(format: Format, content: &[u8]) -> Result<P, Error> {
+    match format {
+        Format::Json => serde_json::from_slice(content).map_err(Error::Json),
+        Format::Toml => {
+            let content = core::str::from_utf8(content).map_err(Error::Utf8)?;
+            toml::from_str(content).map_err(Error::Toml)
+        }
+    }
+}
+
+/// A value built from the stack of a base value, the patch of a [`FileSource`], and an override
+/// patch, rebuilt when the file changes
+///
+/// ```rust
+/// # use struct_patch::{FileSource, Patch, Reloader};
+/// # use serde::Deserialize;
+/// #[derive(Clone, Debug, PartialEq, Patch)]
+/// #[patch(attribute(derive(Clone, Default, Deserialize)))]
+/// struct Config {
+///     log_level: u8,
+///     workers: usize,
+/// }
+///
+/// # let path = std::env::temp_dir()
+/// #     .join(format!("struct-patch-doc-reloader-{}.toml", std::process::id()));
+/// std::fs::write(&path, "log_level = 1").unwrap();
+///
+/// let base = Config { log_level: 0, workers: 1 };
+/// let overrides = struct_patch::patch!(Config { workers: 8 });
+/// let mut config = Reloader::new(base, FileSource::open(&path).unwrap(), overrides);
+/// assert_eq!(config.value(), &Config { log_level: 1, workers: 8 });
+///
+/// std::fs::write(&path, "log_level = 3\nworkers = 2").unwrap();
+/// // the override patch still wins over the file
+/// assert_eq!(config.poll().unwrap(), [ConfigField::LogLevel]);
+/// assert_eq!(config.value(), &Config { log_level: 3, workers: 8 });
+/// assert!(config.poll().unwrap().is_empty());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct Reloader<T, P> {
+    base: T,
+    source: FileSource<P>,
+    overrides: P,
+    value: T,
+}
+
+impl<T, P> Reloader<T, P>
+where
+    T: Patch<P> + Clone,
+    P: Fields + DeserializeOwned + Clone,
+{
+    /// Build the value by applying the file patch and then the override patch on the base value
+    pub fn new(base: T, source: FileSource<P>, overrides: P) -> Self {
+        let value = stack(&base, &source, &overrides);
+        Reloader {
+            base,
+            source,
+            overrides,
+            value,
+        }
+    }
+
+    /// The current value
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// The file source
+    pub fn source(&self) -> &FileSource<P> {
+        &self.source
+    }
+
+    /// Reload the file, rebuild the value if the file is changed, and returns the fields whose
+    /// values are changed
+    pub fn poll(&mut self) -> Result<Vec<P::Field>, Error> {
+        if !self.source.reload()? {
+            return Ok(Vec::new());
+        }
+        let value = stack(&self.base, &self.source, &self.overrides);
+        let previous = core::mem::replace(&mut self.value, value.clone());
+        let diff = value.into_patch_by_diff(previous);
+        Ok(P::FIELDS
+            .iter()
+            .copied()
+            .filter(|f| diff.is_set(*f))
+            .collect())
+    }
+
+    /// Poll the file every `interval`, and call `on_reload` with the value and the changed fields
+    /// after each change of the file, or with the error when the file can not be loaded.  The
+    /// loop stops when `on_reload` returns `false`.
+    pub fn run<F>(&mut self, interval: Duration, mut on_reload: F)
+    where
+        F: FnMut(&T, Result<Vec<P::Field>, Error>) -> bool,
+    {
+        loop {
+            thread::sleep(interval);
+            let keep_running = match self.poll() {
+                Ok(fields) if fields.is_empty() => true,
+                result => on_reload(&self.value, result),
+            };
+            if !keep_running {
+                return;
+            }
+        }
+    }
+}
+
+fn stack<T, P>(base: &T, source: &FileSource<P>, overrides: &P) -> T
+where
+    T: Patch<P> + Clone,
+    P: Clone,
+{
+    let mut value = base.clone();
+    value.apply(source.patch.clone());
+    value.apply(overrides.clone());
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as struct_patch;
+    use crate::Patch;
+    use serde::Deserialize;
+    use std::format;
+    use std::string::String;
+
+    #[derive(Clone, Debug, PartialEq, Patch)]
+    #[patch(attribute(derive(Clone, Debug, Default, Deserialize)))]
+    struct Config {
+        log_level: u8,
+        workers: usize,
+        name: String,
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("struct-patch-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_reloader_json() {
+        let path = temp_path("config.json");
+        fs::write(&path, r#"{ "log_level": 2 }"#).unwrap();
+        let base = Config {
+            log_level: 0,
+            workers: 1,
+            name: String::from("base"),
+        };
+        let overrides = ConfigPatch {
+            name: Some(String::from("override")),
+            ..Default::default()
+        };
+        let mut reloader = Reloader::new(base, FileSource::open(&path).unwrap(), overrides);
+        assert_eq!(reloader.value().log_level, 2);
+        assert_eq!(reloader.value().name, "override");
+        assert!(reloader.poll().unwrap().is_empty());
+
+        // fields only set in the file are reverted to the base value when removed from the file
+        fs::write(&path, r#"{ "workers": 4, "name": "file" }"#).unwrap();
+        assert_eq!(
+            reloader.poll().unwrap(),
+            [ConfigField::LogLevel, ConfigField::Workers]
+        );
+        assert_eq!(
+            reloader.value(),
+            &Config {
+                log_level: 0,
+                workers: 4,
+                name: String::from("override"),
+            }
+        );
+
+        // a broken file keeps the previous patch
+        fs::write(&path, r#"{ "workers": "#).unwrap();
+        assert!(matches!(reloader.poll(), Err(Error::Json(_))));
+        assert_eq!(reloader.source().patch().workers, Some(4));
+        assert_eq!(reloader.value().workers, 4);
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(reloader.poll(), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_reloader_run() {
+        let path = temp_path("config.toml");
+        fs::write(&path, "workers = 2").unwrap();
+        let base = Config {
+            log_level: 0,
+            workers: 1,
+            name: String::new(),
+        };
+        let mut reloader = Reloader::new(
+            base,
+            FileSource::open(&path).unwrap(),
+            ConfigPatch::default(),
+        );
+        fs::write(&path, "workers = 3").unwrap();
+        let mut reloads = Vec::new();
+        reloader.run(Duration::from_millis(1), |config, fields| {
+            reloads.push((config.workers, fields.unwrap()));
+            false
+        });
+        assert_eq!(reloads, [(3, Vec::from([ConfigField::Workers]))]);
+
+        // the invalid UTF-8 is rejected instead of being replaced
+        fs::write(&path, b"name = \"\xff\"").unwrap();
+        assert!(matches!(reloader.poll(), Err(Error::Utf8(_))));
+        assert_eq!(reloader.value().workers, 3);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            FileSource::<ConfigPatch>::open(temp_path("config.yaml")),
+            Err(Error::UnknownFormat(_))
+        ));
+    }
+}